@@ -0,0 +1,234 @@
+use crate::history::HistoryStore;
+use crate::log;
+use crate::model::BillRecord;
+use crate::thai_date::YearMonth;
+use std::collections::HashMap;
+
+// จำนวนเดือนย้อนหลังที่ใช้หาค่าเฉลี่ยหน่วยที่ใช้
+const AVERAGE_MONTHS: i32 = 6;
+
+/// ประเภทความผิดปกติของเลขอ่านมิเตอร์
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnomalyKind {
+    /// ใช้น้ำมากกว่าค่าเฉลี่ยหลายเท่า (อาจมีรอยรั่วหรืออ่านผิด)
+    HighUsage,
+    /// ไม่ได้ใช้น้ำเลย ทั้งที่เคยใช้ (มิเตอร์อาจเสียหรือจดผิด)
+    ZeroUsage,
+    /// เลขอ่านครั้งหลังน้อยกว่าครั้งก่อน
+    NegativeReading,
+}
+
+impl AnomalyKind {
+    pub fn description(self) -> &'static str {
+        match self {
+            AnomalyKind::HighUsage => "ใช้น้ำสูงผิดปกติ",
+            AnomalyKind::ZeroUsage => "ไม่มีการใช้น้ำ",
+            AnomalyKind::NegativeReading => "เลขอ่านครั้งหลังน้อยกว่าครั้งก่อน",
+        }
+    }
+}
+
+/// รายการที่ควรตรวจสอบก่อนออกบิล
+#[derive(Debug)]
+pub struct Anomaly {
+    pub kind: AnomalyKind,
+    pub order: u32,
+    pub meter_number: String,
+    pub name: String,
+    pub units: u32,
+    /// ค่าเฉลี่ยหน่วยที่ใช้ย้อนหลัง (ถ้ามีประวัติ)
+    pub average: Option<f64>,
+    pub previous_reading: u32,
+    pub current_reading: u32,
+}
+
+// ค่าเฉลี่ยหน่วยที่ใช้ของแต่ละมิเตอร์ ในช่วงหลายเดือนก่อนเดือนที่ออกบิล
+fn average_units(history: &HistoryStore, period: YearMonth) -> HashMap<&str, f64> {
+    let since = period.add_months(-AVERAGE_MONTHS);
+    let mut totals: HashMap<&str, (u64, u32)> = HashMap::new();
+    for (month, meter_number, units) in history.monthly_units() {
+        if month >= since && month < period {
+            let total = totals.entry(meter_number.trim()).or_default();
+            total.0 += u64::from(units);
+            total.1 += 1;
+        }
+    }
+    totals
+        .into_iter()
+        .map(|(meter_number, (units, months))| (meter_number, units as f64 / months as f64))
+        .collect()
+}
+
+/// ตรวจหาเลขอ่านที่ผิดปกติ: ใช้น้ำเกิน `factor` เท่าของค่าเฉลี่ย, ไม่มีการใช้น้ำ และเลขอ่านลดลง
+pub fn detect_anomalies(
+    records: &[BillRecord],
+    history: Option<&HistoryStore>,
+    period: YearMonth,
+    factor: f64,
+) -> Vec<Anomaly> {
+    let averages = history
+        .map(|history| average_units(history, period))
+        .unwrap_or_default();
+
+    records
+        .iter()
+        .filter_map(|record| {
+            let average = averages.get(record.meter_number.trim()).copied();
+            let kind = if record.current_reading < record.previous() {
+                AnomalyKind::NegativeReading
+            } else if record.units == 0 {
+                // เฉพาะบ้านที่เคยใช้น้ำแต่เดือนนี้ไม่ใช้เลย
+                // บ้านที่ยังไม่มีประวัติ (เดือนแรก หรือไม่ได้ใช้ไฟล์ประวัติ) ไม่มีอะไรให้เทียบ
+                if !average.is_some_and(|average| average > 0.0) {
+                    return None;
+                }
+                AnomalyKind::ZeroUsage
+            } else if average
+                .is_some_and(|average| average > 0.0 && record.units as f64 > average * factor)
+            {
+                AnomalyKind::HighUsage
+            } else {
+                return None;
+            };
+            Some(Anomaly {
+                kind,
+                order: record.order,
+                meter_number: record.meter_number.clone(),
+                name: record.name.clone(),
+                units: record.units,
+                average,
+                previous_reading: record.previous(),
+                current_reading: record.current_reading,
+            })
+        })
+        .collect()
+}
+
+/// แสดงรายงานรายการที่ควรตรวจสอบก่อนพิมพ์บิล
+pub fn log_report(anomalies: &[Anomaly]) {
+    if anomalies.is_empty() {
+        log::log_info("ไม่พบเลขอ่านมิเตอร์ที่ผิดปกติ");
+        return;
+    }
+    log::log_warn(&format!(
+        "พบเลขอ่านมิเตอร์ที่ควรตรวจสอบ {} รายการ:",
+        anomalies.len()
+    ));
+    for anomaly in anomalies {
+        let average = anomaly
+            .average
+            .map(|average| format!(" เฉลี่ย {:.1} หน่วย", average))
+            .unwrap_or_default();
+        log::log_warn(&format!(
+            "  ลำดับ {} มิเตอร์ {} ({}): {} - เลขอ่าน {} -> {} ใช้ {} หน่วย{}",
+            anomaly.order,
+            anomaly.meter_number,
+            anomaly.name,
+            anomaly.kind.description(),
+            anomaly.previous_reading,
+            anomaly.current_reading,
+            anomaly.units,
+            average
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(meter_number: &str, previous_reading: u32, current_reading: u32) -> BillRecord {
+        BillRecord {
+            order: 1,
+            meter_number: meter_number.to_string(),
+            name: "นายสมชาย".to_string(),
+            previous_reading: Some(previous_reading),
+            current_reading,
+            units: current_reading.saturating_sub(previous_reading),
+            maintenance_fee: 20,
+            rate_per_unit: 2,
+            total_amount: 0,
+            zone: String::new(),
+        }
+    }
+
+    // ประวัติหกเดือนก่อนเดือนที่ออกบิล: M001 ใช้เดือนละ 10 หน่วย, M002 ไม่เคยใช้น้ำ
+    fn history(period: YearMonth) -> HistoryStore {
+        let mut history = HistoryStore::load("tests/ไม่มีไฟล์นี้.csv").unwrap();
+        for back in (1..=AVERAGE_MONTHS).rev() {
+            let reading = 100 + 10 * (AVERAGE_MONTHS - back) as u32;
+            history.record_month(
+                period.add_months(-back),
+                &[
+                    record("M001", reading, reading + 10),
+                    record("M002", 50, 50),
+                ],
+            );
+        }
+        history
+    }
+
+    fn kinds(
+        records: &[BillRecord],
+        history: Option<&HistoryStore>,
+        factor: f64,
+    ) -> Vec<(String, AnomalyKind)> {
+        let period: YearMonth = "2025-10".parse().unwrap();
+        detect_anomalies(records, history, period, factor)
+            .into_iter()
+            .map(|anomaly| (anomaly.meter_number, anomaly.kind))
+            .collect()
+    }
+
+    #[test]
+    fn each_kind_of_anomaly_is_detected() {
+        let history = history("2025-10".parse().unwrap());
+        let records = [
+            record("M001", 200, 190),
+            record("M002", 50, 50),
+            record("M003", 300, 300),
+        ];
+        assert_eq!(
+            kinds(&records, Some(&history), 3.0),
+            [("M001".to_string(), AnomalyKind::NegativeReading)]
+        );
+
+        let records = [record("M001", 160, 160)];
+        assert_eq!(
+            kinds(&records, Some(&history), 3.0),
+            [("M001".to_string(), AnomalyKind::ZeroUsage)]
+        );
+
+        let records = [record("M001", 160, 200)];
+        assert_eq!(
+            kinds(&records, Some(&history), 3.0),
+            [("M001".to_string(), AnomalyKind::HighUsage)]
+        );
+    }
+
+    #[test]
+    fn zero_usage_needs_past_usage() {
+        // ไม่มีประวัติเลย หรือมิเตอร์ที่ไม่เคยใช้น้ำ ไม่ถือว่าผิดปกติ
+        let records = [record("M001", 160, 160), record("M002", 50, 50)];
+        assert!(kinds(&records, None, 3.0).is_empty());
+        let history = history("2025-10".parse().unwrap());
+        assert_eq!(
+            kinds(&records, Some(&history), 3.0),
+            [("M001".to_string(), AnomalyKind::ZeroUsage)]
+        );
+    }
+
+    #[test]
+    fn high_usage_must_exceed_the_factor() {
+        let history = history("2025-10".parse().unwrap());
+        // ค่าเฉลี่ย 10 หน่วย: 30 หน่วยเท่ากับ 3 เท่าพอดี ยังไม่เกิน
+        assert!(kinds(&[record("M001", 160, 190)], Some(&history), 3.0).is_empty());
+        assert_eq!(
+            kinds(&[record("M001", 160, 191)], Some(&history), 3.0),
+            [("M001".to_string(), AnomalyKind::HighUsage)]
+        );
+        assert!(kinds(&[record("M001", 160, 191)], Some(&history), 4.0).is_empty());
+        // ไม่มีประวัติ ไม่มีค่าเฉลี่ยให้เทียบ
+        assert!(kinds(&[record("M001", 160, 1000)], None, 3.0).is_empty());
+    }
+}
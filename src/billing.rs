@@ -0,0 +1,222 @@
+use crate::anomaly::{self, detect_anomalies, Anomaly, AnomalyKind};
+use crate::error::Error;
+use crate::history::{carry_forward, last_readings_from_records, HistoryStore, ReadingMismatch};
+use crate::issued::{IssuedBill, IssuedStore};
+use crate::log;
+use crate::model::BillRecord;
+use crate::pdf_util::BillOptions;
+use crate::thai_date::YearMonth;
+use crate::usage_chart::UsageHistory;
+use crate::Result;
+
+/// ตัวเลือกขั้นตอนออกบิลประจำเดือน ใช้ร่วมกันทั้งบรรทัดคำสั่งและหน้าเว็บ
+#[derive(Debug, Clone)]
+pub struct BillingSettings {
+    /// ไฟล์ประวัติการอ่านมิเตอร์ `None` คือไม่อ่านและไม่บันทึก
+    pub history: Option<String>,
+    /// ทะเบียนบิลที่ออกแล้ว `None` คือไม่บันทึก
+    pub issued: Option<String>,
+    /// ถือว่าใช้น้ำสูงผิดปกติเมื่อเกินค่าเฉลี่ยย้อนหลังกี่เท่า
+    pub anomaly_factor: f64,
+    /// พิมพ์ "โปรดตรวจสอบรอยรั่ว" บนบิลที่ใช้น้ำสูงผิดปกติ
+    pub leak_notice: bool,
+    /// จำนวนเดือนของกราฟหน่วยที่ใช้ย้อนหลังบนบิล
+    pub usage_chart: Option<u8>,
+}
+
+impl Default for BillingSettings {
+    fn default() -> Self {
+        BillingSettings {
+            history: None,
+            issued: None,
+            anomaly_factor: 3.0,
+            leak_notice: false,
+            usage_chart: None,
+        }
+    }
+}
+
+/// บิลประจำเดือนที่ยกเลขอ่านจากเดือนก่อนและตรวจเลขอ่านผิดปกติแล้ว
+pub struct MonthlyBills {
+    pub period: YearMonth,
+    pub records: Vec<BillRecord>,
+    /// เลขอ่านครั้งก่อนที่ไม่ตรงกับเลขอ่านครั้งหลังของเดือนก่อน
+    pub mismatches: Vec<ReadingMismatch>,
+    pub anomalies: Vec<Anomaly>,
+    history: Option<HistoryStore>,
+}
+
+/// เตรียมบิลประจำเดือน: ยกเลขอ่านครั้งหลังของเดือนก่อน (จาก `previous` หรือไฟล์ประวัติ) มาเป็นเลขอ่านครั้งก่อน
+/// คำนวณหน่วยและยอดเงิน ตรวจเลขอ่านผิดปกติ แล้วเติมคำเตือนรอยรั่วและกราฟหน่วยที่ใช้ลงใน `options`
+/// ถ้ามีแถวที่ยังไม่มีเลขอ่านครั้งก่อน (ไม่ได้กรอกและไม่มีในเดือนก่อน) จะคืนข้อผิดพลาด Validation
+pub fn prepare_bills(
+    mut records: Vec<BillRecord>,
+    period: YearMonth,
+    previous: Option<&[BillRecord]>,
+    settings: &BillingSettings,
+    options: &mut BillOptions,
+) -> Result<MonthlyBills> {
+    let history = match &settings.history {
+        Some(path) => Some(HistoryStore::load(path)?),
+        None => None,
+    };
+    let last_readings = match (previous, &history) {
+        (Some(previous), _) => Some(last_readings_from_records(previous)),
+        (None, Some(history)) => Some(history.last_readings(period)),
+        (None, None) => None,
+    };
+    let mismatches = match last_readings {
+        Some(last_readings) => carry_forward(&mut records, &last_readings),
+        None => Vec::new(),
+    };
+    if !mismatches.is_empty() {
+        log::log_warn(&format!(
+            "พบเลขอ่านครั้งก่อนไม่ตรงกับเดือนก่อน {} รายการ โปรดตรวจสอบ:",
+            mismatches.len()
+        ));
+        for mismatch in &mismatches {
+            log::log_warn(&format!(
+                "  ลำดับ {} มิเตอร์ {} ({}): ในไฟล์ {} / เดือนก่อน {}",
+                mismatch.order,
+                mismatch.meter_number,
+                mismatch.name,
+                mismatch.supplied,
+                mismatch.stored
+            ));
+        }
+    }
+
+    // คำนวณหน่วยและยอดเงินหลังยกเลขอ่านแล้ว แถวที่ยังไม่มีเลขอ่านครั้งก่อนออกบิลไม่ได้
+    let incomplete: Vec<String> = records
+        .iter_mut()
+        .filter_map(|record| {
+            let message = record.complete().err()?;
+            Some(format!(
+                "ลำดับ {} มิเตอร์ {} ({}): {}",
+                record.order, record.meter_number, record.name, message
+            ))
+        })
+        .collect();
+    if !incomplete.is_empty() {
+        return Err(Error::Validation(format!(
+            "ออกบิลไม่ได้ {} รายการ:\n{}",
+            incomplete.len(),
+            incomplete.join("\n")
+        )));
+    }
+
+    // ตรวจเลขอ่านที่ผิดปกติก่อนพิมพ์บิล
+    let anomalies = detect_anomalies(&records, history.as_ref(), period, settings.anomaly_factor);
+    anomaly::log_report(&anomalies);
+    if settings.leak_notice {
+        options.leak_notices = anomalies
+            .iter()
+            .filter(|anomaly| anomaly.kind == AnomalyKind::HighUsage)
+            .map(|anomaly| anomaly.meter_number.trim().to_string())
+            .collect();
+    }
+
+    options.usage_chart = match (settings.usage_chart, &history) {
+        (Some(months), Some(history)) => Some(UsageHistory::build(
+            history,
+            period,
+            months.into(),
+            &records,
+        )?),
+        (Some(_), None) => {
+            log::log_warn("ไม่ได้ใช้ไฟล์ประวัติ จึงไม่พิมพ์กราฟหน่วยที่ใช้");
+            None
+        }
+        (None, _) => None,
+    };
+
+    Ok(MonthlyBills {
+        period,
+        records,
+        mismatches,
+        anomalies,
+        history,
+    })
+}
+
+impl MonthlyBills {
+    /// บันทึกเลขอ่านของเดือนนี้ลงไฟล์ประวัติ และบิลที่ออกลงทะเบียนบิล
+    /// `issued` คือบิลที่ออกจริงเมื่อเลือกออกเฉพาะบางราย (`None` คือทุกรายการ)
+    pub fn save(
+        &mut self,
+        issued: Option<&[BillRecord]>,
+        for_month: &str,
+        options: &BillOptions,
+        settings: &BillingSettings,
+    ) -> Result<()> {
+        if let (Some(history), Some(path)) = (self.history.as_mut(), &settings.history) {
+            history.record_month(self.period, &self.records);
+            history.save(path)?;
+        }
+        // ทะเบียนบิลที่ออกในรอบนี้ สำหรับพิมพ์ซ้ำเมื่อบิลหาย
+        if let Some(path) = &settings.issued {
+            let mut store = IssuedStore::load(path)?;
+            store.record(
+                issued
+                    .unwrap_or(&self.records)
+                    .iter()
+                    .map(|record| IssuedBill::new(record, for_month, options)),
+            );
+            store.save(path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(
+        meter_number: &str,
+        previous_reading: Option<u32>,
+        current_reading: u32,
+    ) -> BillRecord {
+        BillRecord {
+            order: 1,
+            meter_number: meter_number.to_string(),
+            name: "นายสมชาย".to_string(),
+            previous_reading,
+            current_reading,
+            units: 0,
+            maintenance_fee: 20,
+            rate_per_unit: 2,
+            total_amount: 0,
+            zone: String::new(),
+        }
+    }
+
+    #[test]
+    fn bills_are_calculated_after_carry_forward() {
+        let period: YearMonth = "2025-10".parse().unwrap();
+        let previous = [record("M001", Some(1100), 1200)];
+        let bills = prepare_bills(
+            vec![record("M001", None, 1250)],
+            period,
+            Some(&previous),
+            &BillingSettings::default(),
+            &mut BillOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(bills.records[0].units, 50);
+        assert_eq!(bills.records[0].total_amount, 120);
+    }
+
+    #[test]
+    fn rows_without_any_previous_reading_are_rejected() {
+        let period: YearMonth = "2025-10".parse().unwrap();
+        let result = prepare_bills(
+            vec![record("M001", Some(1200), 1250), record("M002", None, 830)],
+            period,
+            None,
+            &BillingSettings::default(),
+            &mut BillOptions::default(),
+        );
+        assert!(matches!(result, Err(Error::Validation(message)) if message.contains("M002")));
+    }
+}
@@ -0,0 +1,324 @@
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
+#[cfg(feature = "email")]
+use papa_moo_3_bills::email::SmtpSecurity;
+#[cfg(feature = "notify")]
+use papa_moo_3_bills::notify::NotifyChannel;
+use papa_moo_3_bills::pdf_util::CopyMode;
+use papa_moo_3_bills::printer::Duplex;
+#[cfg(feature = "notify")]
+use papa_moo_3_bills::promptpay::PromptPayId;
+use papa_moo_3_bills::thai_date::{parse_date, YearMonth};
+use papa_moo_3_bills::usage_chart::{MAX_MONTHS, MIN_MONTHS};
+
+/// สร้างใบเสร็จค่าน้ำประปาหมู่บ้าน หมู่ 3 จากไฟล์ CSV
+#[derive(Debug, Parser)]
+#[command(name = "papa_moo_3_bills", version)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// ไฟล์ข้อมูลการอ่านมิเตอร์ของเดือนนี้ (.csv, .xlsx, .xls, .ods, .json หรือ .jsonl)
+    #[arg(short, long, default_value = "./input-csv/bills_oct_68.csv")]
+    pub input: String,
+
+    /// ชื่อชีตในไฟล์ Excel/ODS (ค่าเริ่มต้นคือชีตแรก)
+    #[arg(long)]
+    pub sheet: Option<String>,
+
+    /// ไฟล์ CSV สองคอลัมน์ (ชื่อคอลัมน์ในไฟล์, ชื่อคอลัมน์ของโปรแกรม) สำหรับหัวคอลัมน์ที่ต่างจากมาตรฐาน
+    #[arg(long, global = true)]
+    pub header_aliases: Option<String>,
+
+    /// ข้ามแถวที่ข้อมูลผิดพลาดแล้วสร้างบิลจากแถวที่ถูกต้อง (แถวที่ผิดจะบันทึกไว้ในไฟล์แยก)
+    #[arg(long, global = true)]
+    pub skip_invalid: bool,
+
+    /// ไฟล์ CSV สำหรับบันทึกแถวที่ผิดพลาด (ค่าเริ่มต้น: <ชื่อไฟล์>_rejected.csv)
+    #[arg(long, global = true)]
+    pub rejected: Option<String>,
+
+    /// ประจำเดือนของบิล เช่น "ต.ค. 68", "ตุลาคม 2568" หรือ "2025-10" (ค่าเริ่มต้นคือเดือนของวันออกบิล)
+    #[arg(short, long)]
+    pub month: Option<YearMonth>,
+
+    /// ไฟล์ PDF ผลลัพธ์
+    #[arg(short, long, default_value = "bills/plumbing_oct_68.pdf")]
+    pub output: String,
+
+    /// พิมพ์สำเนาสำหรับสำนักงานด้วยหรือไม่ (single, same-sheet, separate)
+    #[arg(long, value_enum, default_value_t = CopyMode::Single)]
+    pub copies: CopyMode,
+
+    /// พิมพ์ส่วนต่อท้ายสำหรับฉีกเก็บเมื่อรับชำระเงินสด
+    #[arg(long)]
+    pub payment_stub: bool,
+
+    /// จำนวนวันนับจากวันออกบิลถึงวันครบกำหนดชำระ
+    #[arg(long, default_value_t = 15)]
+    pub due_days: u32,
+
+    /// วันออกบิล เช่น "5 ต.ค. 68" หรือ "2025-10-05" (ค่าเริ่มต้นคือวันนี้) ใช้เป็นวันที่บนใบจดเลขมิเตอร์ด้วย
+    /// ระบุวันเดิมเพื่อสร้างไฟล์ PDF ให้เหมือนเดิมทุกไบต์
+    #[arg(long, global = true, value_parser = parse_date)]
+    pub issue_date: Option<NaiveDate>,
+
+    /// พิมพ์กราฟหน่วยที่ใช้ย้อนหลัง 6-12 เดือนบนบิลจากไฟล์ประวัติ
+    #[arg(
+        long,
+        value_name = "MONTHS",
+        value_parser = clap::value_parser!(u8).range(MIN_MONTHS as i64..=MAX_MONTHS as i64)
+    )]
+    pub usage_chart: Option<u8>,
+
+    /// ถือว่าใช้น้ำสูงผิดปกติเมื่อเกินค่าเฉลี่ยย้อนหลังกี่เท่า
+    #[arg(long, default_value_t = 3.0)]
+    pub anomaly_factor: f64,
+
+    /// พิมพ์ "โปรดตรวจสอบรอยรั่ว" บนบิลที่ใช้น้ำสูงผิดปกติ
+    #[arg(long)]
+    pub leak_notice: bool,
+
+    /// พิมพ์วันที่บนบิลเป็นเลขไทย
+    #[arg(long)]
+    pub thai_digits: bool,
+
+    /// สร้าง PDF แยกไฟล์ละหนึ่งบิลแทนไฟล์รวม ตั้งชื่อตามรูปแบบ ใช้ {month} {order} {meter} {name} ได้
+    /// เช่น "bills/{month}/{order}-{meter}.pdf"
+    #[arg(long, value_name = "PATTERN")]
+    pub per_customer: Option<String>,
+
+    /// พิมพ์เฉพาะบิลของผู้ใช้น้ำที่ระบุ ด้วยลำดับหรือเลขมิเตอร์ คั่นด้วยจุลภาค เช่น "3,12,M045"
+    #[arg(long, value_delimiter = ',')]
+    pub only: Vec<String>,
+
+    /// บันทึกบิลที่คำนวณแล้วเป็น JSON (.json) หรือ JSON Lines (.jsonl) ระบุ "-" เพื่อเขียนออก stdout
+    #[arg(long)]
+    pub json_output: Option<String>,
+
+    /// บันทึกบัญชีรายรับของเดือนพร้อมแถวรวม เป็น .xlsx หรือ CSV สำหรับลงสมุดบัญชีหมู่บ้าน
+    #[arg(long)]
+    pub ledger: Option<String>,
+
+    /// ไฟล์ PDF สรุปการเก็บเงินประจำเดือน (A4 แนวนอน) สำหรับคณะกรรมการ
+    #[arg(long)]
+    pub summary: Option<String>,
+
+    /// ไฟล์ CSV/Excel/ODS/JSON ของเดือนก่อน ใช้เติมเลขอ่านครั้งก่อนแทนไฟล์ประวัติ
+    #[arg(long)]
+    pub previous: Option<String>,
+
+    /// ชื่อชีตในไฟล์ของเดือนก่อน
+    #[arg(long)]
+    pub previous_sheet: Option<String>,
+
+    /// ไฟล์ประวัติการอ่านมิเตอร์
+    #[arg(long, global = true, default_value = "history/readings.csv")]
+    pub history: String,
+
+    /// ไม่อ่านและไม่บันทึกไฟล์ประวัติและทะเบียนบิล
+    #[arg(long, global = true)]
+    pub no_history: bool,
+
+    /// ทะเบียนบิลที่ออกแล้ว (JSON Lines) สำหรับพิมพ์ซ้ำ
+    #[arg(long, global = true, default_value = "history/issued.jsonl")]
+    pub issued: String,
+
+    /// ส่งไฟล์ PDF ไปพิมพ์ทันที: ชื่อเครื่องพิมพ์ใน CUPS หรือที่อยู่ IPP เช่น ipp://192.168.1.20/ipp/print
+    #[arg(long, global = true)]
+    pub printer: Option<String>,
+
+    /// จำนวนชุดที่พิมพ์
+    #[arg(long, global = true, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub print_copies: u32,
+
+    /// พิมพ์สองหน้า (long-edge, short-edge)
+    #[arg(long, global = true, value_enum)]
+    pub duplex: Option<Duplex>,
+
+    /// ถาดกระดาษ ชื่อตามที่เครื่องพิมพ์รองรับ เช่น tray-2
+    #[arg(long, global = true)]
+    pub tray: Option<String>,
+
+    #[cfg(feature = "email")]
+    #[command(flatten)]
+    pub email: EmailArgs,
+
+    #[cfg(feature = "notify")]
+    #[command(flatten)]
+    pub notify: NotifyArgs,
+}
+
+/// ตัวเลือกการส่งใบเสร็จทางอีเมลหลังออกบิล
+#[cfg(feature = "email")]
+#[derive(Debug, clap::Args)]
+#[command(next_help_heading = "ส่งบิลทางอีเมล")]
+pub struct EmailArgs {
+    /// ไฟล์ CSV รายชื่ออีเมล (คอลัมน์ เลขมิเตอร์, อีเมล) ระบุแล้วจะส่งใบเสร็จของแต่ละรายทางอีเมล
+    #[arg(long, requires_all = ["smtp_host", "smtp_from"])]
+    pub email_contacts: Option<String>,
+
+    /// ส่งไฟล์สรุปประจำเดือน (--summary) ถึงอีเมลเหล่านี้ คั่นด้วยจุลภาค
+    #[arg(long, value_delimiter = ',', requires_all = ["summary", "smtp_host", "smtp_from"])]
+    pub email_summary_to: Vec<String>,
+
+    /// เซิร์ฟเวอร์ SMTP เช่น smtp.gmail.com
+    #[arg(long)]
+    pub smtp_host: Option<String>,
+
+    /// พอร์ตของเซิร์ฟเวอร์ SMTP
+    #[arg(long, default_value_t = 587)]
+    pub smtp_port: u16,
+
+    /// การเข้ารหัสการเชื่อมต่อ (starttls, tls, none)
+    #[arg(long, value_enum, default_value_t = SmtpSecurity::Starttls)]
+    pub smtp_security: SmtpSecurity,
+
+    /// ชื่อผู้ใช้สำหรับเข้าสู่ระบบ SMTP
+    #[arg(long)]
+    pub smtp_user: Option<String>,
+
+    /// รหัสผ่าน SMTP (ควรตั้งผ่านตัวแปร SMTP_PASSWORD แทนการพิมพ์ในบรรทัดคำสั่ง)
+    #[arg(long, env = "SMTP_PASSWORD", hide_env_values = true)]
+    pub smtp_password: Option<String>,
+
+    /// ผู้ส่ง เช่น "ประปาหมู่ 3 <water@example.com>"
+    #[arg(long)]
+    pub smtp_from: Option<String>,
+
+    /// ไฟล์แม่แบบอีเมล บรรทัดแรกเป็นหัวเรื่อง ที่เหลือเป็นเนื้อความ
+    /// ใช้ {name} {meter} {month} {bill_number} {units} {amount} {due_date} ได้
+    #[arg(long)]
+    pub email_template: Option<String>,
+
+    /// ไฟล์ CSV บันทึกสถานะการส่งอีเมล (บิลที่ส่งสำเร็จแล้วจะไม่ส่งซ้ำ)
+    #[arg(long, default_value = "history/email_status.csv")]
+    pub email_status: String,
+}
+
+/// ตัวเลือกการแจ้งบิลทาง LINE หรือ webhook หลังออกบิล
+#[cfg(feature = "notify")]
+#[derive(Debug, clap::Args)]
+#[command(next_help_heading = "แจ้งบิลทาง LINE / webhook")]
+pub struct NotifyArgs {
+    /// ช่องทางแจ้งบิลของแต่ละราย (line, webhook)
+    #[arg(long, value_enum)]
+    pub notify: Option<NotifyChannel>,
+
+    /// ที่อยู่ปลายทาง (ค่าเริ่มต้นของ line คือ LINE Messaging API, webhook ต้องระบุ)
+    #[arg(long, required_if_eq("notify", "webhook"))]
+    pub notify_url: Option<String>,
+
+    /// token ของปลายทาง เช่น channel access token ของ LINE (ควรตั้งผ่านตัวแปร NOTIFY_TOKEN)
+    #[arg(
+        long,
+        env = "NOTIFY_TOKEN",
+        hide_env_values = true,
+        required_if_eq("notify", "line")
+    )]
+    pub notify_token: Option<String>,
+
+    /// ไฟล์ CSV รายชื่อผู้รับ (คอลัมน์ เลขมิเตอร์, ผู้รับ) เช่น LINE user ID ของแต่ละมิเตอร์
+    #[arg(long, required_if_eq("notify", "line"))]
+    pub notify_contacts: Option<String>,
+
+    /// เบอร์มือถือหรือเลขประจำตัว 13 หลักของบัญชีพร้อมเพย์ ระบุแล้วจะแนบ QR ตามยอดเงินของแต่ละบิล
+    #[arg(long)]
+    pub promptpay: Option<PromptPayId>,
+
+    /// โฟลเดอร์บันทึกภาพ QR พร้อมเพย์ (ชื่อไฟล์เป็น <เลขที่บิล>.png)
+    #[arg(long, default_value = "bills/qr")]
+    pub qr_dir: String,
+
+    /// ลิงก์ https ของโฟลเดอร์ภาพ QR บนเว็บ (LINE แสดงภาพจากลิงก์เท่านั้น)
+    #[arg(long)]
+    pub qr_base_url: Option<String>,
+
+    /// จำนวนครั้งที่ลองส่งซ้ำเมื่อส่งไม่สำเร็จ
+    #[arg(long, default_value_t = 3)]
+    pub notify_retries: u32,
+
+    /// แสดงข้อความที่จะส่งโดยไม่ส่งจริง (ไม่บันทึกภาพ QR และสถานะการส่ง)
+    #[arg(long)]
+    pub notify_dry_run: bool,
+
+    /// ไฟล์ CSV บันทึกสถานะการแจ้งบิล (บิลที่แจ้งสำเร็จแล้วจะไม่แจ้งซ้ำ)
+    #[arg(long, default_value = "history/notify_status.csv")]
+    pub notify_status: String,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// สร้างใบจดเลขมิเตอร์สำหรับวันอ่านมิเตอร์ จากไฟล์ CSV ของเดือนก่อน
+    RouteSheet {
+        /// ไฟล์ CSV/Excel/ODS ของเดือนก่อน (คอลัมน์ `โซน` ใช้แยกเส้นทาง)
+        #[arg(short, long)]
+        input: String,
+
+        /// ชื่อชีตในไฟล์ Excel/ODS
+        #[arg(long)]
+        sheet: Option<String>,
+
+        /// เดือนที่จะออกจดเลขมิเตอร์ เช่น "พ.ย. 68"
+        #[arg(short, long)]
+        month: YearMonth,
+
+        /// ไฟล์ PDF ผลลัพธ์
+        #[arg(short, long, default_value = "bills/route_sheet.pdf")]
+        output: String,
+    },
+
+    /// พิมพ์บิลที่ออกไปแล้วซ้ำจากทะเบียนบิล (วันออกบิลและยอดเงินเหมือนต้นฉบับ)
+    Reprint {
+        /// เลขที่บิล หรือเลขมิเตอร์ (ได้บิลล่าสุดของมิเตอร์นั้น)
+        bill: String,
+
+        /// ประจำเดือนของบิลที่ต้องการ เมื่อค้นหาด้วยเลขมิเตอร์
+        #[arg(short, long)]
+        month: Option<YearMonth>,
+
+        /// ไฟล์ PDF ผลลัพธ์ (ค่าเริ่มต้น: bills/reprint_<เลขที่บิล>.pdf)
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// พิมพ์สำเนาสำหรับสำนักงานด้วยหรือไม่ (single, same-sheet, separate)
+        #[arg(long, value_enum, default_value_t = CopyMode::Single)]
+        copies: CopyMode,
+    },
+
+    /// กรอกเลขอ่านมิเตอร์ของเดือนนี้ทีละรายในหน้าจอเทอร์มินัล แล้วบันทึกเป็นไฟล์ CSV
+    #[cfg(feature = "tui")]
+    Enter {
+        /// ไฟล์ CSV/Excel/ODS ของเดือนก่อน (รายชื่อผู้ใช้น้ำ เลขอ่านครั้งหลังจะเป็นเลขอ่านครั้งก่อน)
+        #[arg(short, long)]
+        input: String,
+
+        /// ชื่อชีตในไฟล์ Excel/ODS
+        #[arg(long)]
+        sheet: Option<String>,
+
+        /// เดือนที่จดเลขมิเตอร์ เช่น "พ.ย. 68"
+        #[arg(short, long)]
+        month: YearMonth,
+
+        /// ไฟล์ CSV ของเดือนนี้
+        #[arg(short, long)]
+        output: String,
+
+        /// สร้างใบเสร็จต่อทันทีหลังบันทึก (ใช้ตัวเลือกใบเสร็จของคำสั่งหลัก เช่น --copies)
+        #[arg(long)]
+        bills: bool,
+    },
+
+    /// เปิดหน้าเว็บในเครื่องสำหรับอัปโหลดไฟล์ข้อมูล ตรวจข้อมูล และดาวน์โหลดใบเสร็จ
+    #[cfg(feature = "web")]
+    Serve {
+        /// ที่อยู่และพอร์ตของหน้าเว็บ (127.0.0.1 เปิดได้เฉพาะเครื่องนี้)
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        address: String,
+
+        /// ไฟล์ JSON ชุดตัวเลือกใบเสร็จที่ตั้งชื่อไว้ ให้เลือกในหน้าเว็บ (ดู README)
+        #[arg(long)]
+        profiles: Option<String>,
+    },
+}
@@ -1,20 +1,395 @@
+use crate::error::Error;
+use crate::input::{complete_record, HeaderAliases, ReadReport, RowError};
 use crate::log;
-use crate::model::BillRecord;
-use std::error::Error;
+use crate::model::{parse_number, BillRecord, CSV_COLUMNS, NUMBER_COLUMNS};
+use crate::Result;
+use csv::StringRecord;
+use encoding_rs::{Encoding, UTF_8, WINDOWS_874};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
 
-pub fn read_csv_file(file_path: &str) -> Result<Vec<BillRecord>, Box<dyn Error>> {
+const DELIMITER_CANDIDATES: [u8; 4] = [b',', b';', b'\t', b'|'];
+
+pub fn read_csv_file(file_path: &str, aliases: &HeaderAliases) -> Result<ReadReport> {
     log::log_info(&format!("กำลังอ่านไฟล์ CSV: {}", file_path));
-    let mut reader = csv::Reader::from_path(file_path)?;
-    let records: Vec<BillRecord> = reader.deserialize().collect::<Result<_, _>>()?;
-    log::log_info(&format!("อ่านข้อมูลสำเร็จ จำนวน {} รายการ", records.len()));
-
-    for (i, record) in records.iter().enumerate() {
-        log::log_debug(&format!(
-            "รายการที่ {}: {} - {}",
-            i + 1,
-            record.meter_number,
-            record.name
+    let text = decode_csv_bytes(&fs::read(file_path)?);
+    let delimiter = sniff_delimiter(&text);
+    log::log_debug(&format!("ตัวคั่นคอลัมน์: {:?}", delimiter as char));
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let headers: StringRecord = reader
+        .headers()?
+        .iter()
+        .map(|header| aliases.canonical(header))
+        .collect();
+    log::log_debug(&format!("หัวคอลัมน์: {:?}", headers));
+
+    let mut rows = Vec::new();
+    for row in reader.records() {
+        let row = row?;
+        let line = row.position().map_or(0, |position| position.line());
+        rows.push((line, row));
+    }
+
+    let report = deserialize_rows(&headers, rows);
+    log::log_info(&format!(
+        "อ่านข้อมูลสำเร็จ จำนวน {} รายการ",
+        report.records.len()
+    ));
+    Ok(report)
+}
+
+/// บันทึกข้อมูลบิลเป็น CSV ด้วยหัวคอลัมน์ภาษาไทยแบบเดียวกับไฟล์ที่โปรแกรมอ่าน (`CSV_COLUMNS`)
+/// ใส่ BOM ให้ Excel เปิดภาษาไทยได้ถูกต้อง
+pub fn write_csv_file(records: &[BillRecord], output_path: &str) -> Result<()> {
+    log::log_info(&format!("กำลังบันทึกไฟล์ CSV: {}", output_path));
+    if let Some(dir) = Path::new(output_path).parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = BufWriter::new(File::create(output_path)?);
+    file.write_all("\u{feff}".as_bytes())?;
+    let mut writer = csv::Writer::from_writer(file);
+    writer.write_record(CSV_COLUMNS)?;
+    for record in records {
+        writer.write_record([
+            record.order.to_string(),
+            record.meter_number.clone(),
+            record.name.clone(),
+            record
+                .previous_reading
+                .map(|reading| reading.to_string())
+                .unwrap_or_default(),
+            record.current_reading.to_string(),
+            record.units.to_string(),
+            record.maintenance_fee.to_string(),
+            record.rate_per_unit.to_string(),
+            record.total_amount.to_string(),
+            record.zone.clone(),
+        ])?;
+    }
+    writer.flush()?;
+    log::log_info(&format!("บันทึกข้อมูล {} รายการ", records.len()));
+    Ok(())
+}
+
+/// แปลงแถวข้อมูล (เลขบรรทัด, ค่าในแถว) เป็น BillRecord เก็บแถวที่ผิดพลาดไว้รายงานทีเดียว
+pub fn deserialize_rows(
+    headers: &StringRecord,
+    rows: impl IntoIterator<Item = (u64, StringRecord)>,
+) -> ReadReport {
+    let mut records: Vec<BillRecord> = Vec::new();
+    let mut errors = Vec::new();
+
+    for (line, row) in rows {
+        // ข้ามแถวว่าง เช่น ",,,,," ท้ายไฟล์ที่ Excel มักเพิ่มมา
+        if row.iter().all(|field| field.is_empty()) {
+            continue;
+        }
+        match row.deserialize::<BillRecord>(Some(headers)) {
+            Ok(mut record) => match complete_record(&mut record) {
+                Ok(()) => {
+                    log::log_debug(&format!(
+                        "บรรทัด {}: {} - {}",
+                        line, record.meter_number, record.name
+                    ));
+                    records.push(record);
+                }
+                Err(message) => errors.push(RowError {
+                    line,
+                    column: Some("จำนวน".to_string()),
+                    value: None,
+                    message,
+                    raw: row.iter().map(str::to_string).collect(),
+                }),
+            },
+            Err(error) => errors.push(row_error(headers, line, &row, &error)),
+        }
+    }
+
+    ReadReport {
+        headers: headers.iter().map(str::to_string).collect(),
+        records,
+        errors,
+    }
+}
+
+fn row_error(
+    headers: &StringRecord,
+    line: u64,
+    row: &StringRecord,
+    error: &csv::Error,
+) -> RowError {
+    let (field, message) = match error.kind() {
+        csv::ErrorKind::Deserialize { err, .. } => (
+            err.field().map(|field| field as usize),
+            describe_deserialize_error(err.kind()),
+        ),
+        _ => (None, error.to_string()),
+    };
+    // ข้อผิดพลาดจากตัวแปลงตัวเลขของเราไม่มีตำแหน่งคอลัมน์ ให้หาคอลัมน์ตัวเลขที่แปลงไม่ได้เอง
+    let field = field.or_else(|| invalid_number_field(headers, row));
+
+    let value = field.and_then(|field| row.get(field)).map(str::to_string);
+    let message = match value.as_deref() {
+        Some("") => "ไม่ได้กรอกข้อมูล".to_string(),
+        _ => message,
+    };
+
+    RowError {
+        line,
+        column: field
+            .and_then(|field| headers.get(field))
+            .map(str::to_string),
+        value,
+        message,
+        raw: row.iter().map(str::to_string).collect(),
+    }
+}
+
+fn invalid_number_field(headers: &StringRecord, row: &StringRecord) -> Option<usize> {
+    headers.iter().zip(row.iter()).position(|(header, value)| {
+        let Some(&(_, required)) = NUMBER_COLUMNS.iter().find(|(name, _)| *name == header) else {
+            return false;
+        };
+        match parse_number(value) {
+            Ok(Some(_)) => false,
+            Ok(None) => required,
+            Err(_) => true,
+        }
+    })
+}
+
+fn describe_deserialize_error(kind: &csv::DeserializeErrorKind) -> String {
+    match kind {
+        csv::DeserializeErrorKind::Message(message) => match message.strip_prefix("missing field ")
+        {
+            Some(field) => format!("ไม่พบคอลัมน์ {}", field),
+            None => message.clone(),
+        },
+        csv::DeserializeErrorKind::Unsupported(message) => message.clone(),
+        csv::DeserializeErrorKind::UnexpectedEndOfRow => "ข้อมูลในแถวไม่ครบทุกคอลัมน์".to_string(),
+        csv::DeserializeErrorKind::InvalidUtf8(_) => "ข้อความไม่ใช่ UTF-8".to_string(),
+        csv::DeserializeErrorKind::ParseBool(_) => "ไม่ใช่ค่า true/false".to_string(),
+        csv::DeserializeErrorKind::ParseInt(_) => "ไม่ใช่ตัวเลขจำนวนเต็ม".to_string(),
+        csv::DeserializeErrorKind::ParseFloat(_) => "ไม่ใช่ตัวเลข".to_string(),
+    }
+}
+
+/// อ่านไฟล์รายชื่อผู้รับ (CSV) คืนค่า เลขมิเตอร์ -> ผู้รับ
+/// `columns` คือชื่อคอลัมน์ผู้รับที่ยอมรับ เช่น อีเมล/email ชื่อแรกใช้ในข้อความแจ้งเตือน
+/// ผู้รับหลายคนใส่ในช่องเดียวกันคั่นด้วย ; หรือใส่หลายแถวก็ได้
+pub fn read_contacts(path: &str, columns: &[&str]) -> Result<HashMap<String, Vec<String>>> {
+    let text = decode_csv_bytes(&fs::read(path)?);
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(sniff_delimiter(&text))
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let headers = reader.headers()?.clone();
+    let find = |names: &[&str]| {
+        headers
+            .iter()
+            .position(|header| names.iter().any(|name| header.eq_ignore_ascii_case(name)))
+    };
+    let (Some(meter_column), Some(value_column)) =
+        (find(&["เลขมิเตอร์", "meter", "meter_number"]), find(columns))
+    else {
+        return Err(Error::Validation(format!(
+            "ไฟล์รายชื่อ {} ต้องมีคอลัมน์ เลขมิเตอร์ และ {}",
+            path,
+            columns.first().unwrap_or(&"ผู้รับ")
+        )));
+    };
+
+    let mut contacts: HashMap<String, Vec<String>> = HashMap::new();
+    for row in reader.records() {
+        let row = row?;
+        let meter = row.get(meter_column).unwrap_or_default();
+        if meter.is_empty() {
+            continue;
+        }
+        let values = row
+            .get(value_column)
+            .unwrap_or_default()
+            .split(';')
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string);
+        contacts
+            .entry(meter.to_string())
+            .or_default()
+            .extend(values);
+    }
+    log::log_info(&format!("อ่านรายชื่อผู้รับ {} มิเตอร์ ({})", contacts.len(), path));
+    Ok(contacts)
+}
+
+/// แปลงไฟล์เป็นข้อความ UTF-8 ตัด BOM ออก
+/// ไฟล์ที่ไม่ใช่ UTF-8 ถือว่าเป็น Windows-874/TIS-620 (Excel บน Windows ภาษาไทย)
+pub fn decode_csv_bytes(bytes: &[u8]) -> String {
+    let (encoding, bom_length) = match Encoding::for_bom(bytes) {
+        Some(found) => found,
+        None if std::str::from_utf8(bytes).is_ok() => (UTF_8, 0),
+        None => (WINDOWS_874, 0),
+    };
+    log::log_debug(&format!("การเข้ารหัสไฟล์: {}", encoding.name()));
+    let (text, had_errors) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+    if had_errors {
+        log::log_warn(&format!("พบอักขระที่แปลงไม่ได้ในไฟล์ ({})", encoding.name()));
+    }
+    text.into_owned()
+}
+
+/// เลือกตัวคั่นคอลัมน์ที่พบมากที่สุดในบรรทัดหัวตาราง
+pub fn sniff_delimiter(text: &str) -> u8 {
+    let header_line = text
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or_default();
+    DELIMITER_CANDIDATES
+        .iter()
+        .copied()
+        .max_by_key(|&delimiter| {
+            header_line
+                .bytes()
+                .filter(|&byte| byte == delimiter)
+                .count()
+        })
+        .filter(|&delimiter| header_line.as_bytes().contains(&delimiter))
+        .unwrap_or(b',')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn written_csv_reads_back_the_same_records() {
+        // หน่วยและยอดเงินไม่ตรงกับที่คำนวณได้ (30 หน่วย 80 บาท) ถ้าอ่านคอลัมน์ไม่ได้จะได้ค่าที่คำนวณใหม่แทน
+        let record = BillRecord {
+            order: 7,
+            meter_number: "M007".to_string(),
+            name: "นางสาวดี".to_string(),
+            previous_reading: Some(800),
+            current_reading: 830,
+            units: 31,
+            maintenance_fee: 20,
+            rate_per_unit: 2,
+            total_amount: 99,
+            zone: "หมู่ 3 ฝั่งตะวันออก".to_string(),
+        };
+        let path = std::env::temp_dir().join(format!(
+            "papa_moo_3_bills_roundtrip_{}.csv",
+            std::process::id()
+        ));
+        let path = path.to_string_lossy();
+        write_csv_file(std::slice::from_ref(&record), &path).unwrap();
+        let report = read_csv_file(&path, &HeaderAliases::default()).unwrap();
+        fs::remove_file(path.as_ref()).unwrap();
+
+        // หัวคอลัมน์ที่เขียนต้องตรงกับชื่อที่ BillRecord อ่าน ค่าทุกช่องจึงกลับมาครบ
+        assert!(report.errors.is_empty());
+        assert_eq!(report.headers, CSV_COLUMNS);
+        let read = &report.records[0];
+        assert_eq!(
+            (read.order, read.meter_number.as_str(), read.name.as_str()),
+            (
+                record.order,
+                record.meter_number.as_str(),
+                record.name.as_str()
+            )
+        );
+        assert_eq!(
+            (read.previous_reading, read.current_reading, read.units),
+            (
+                record.previous_reading,
+                record.current_reading,
+                record.units
+            )
+        );
+        assert_eq!(
+            (read.maintenance_fee, read.rate_per_unit, read.total_amount),
+            (
+                record.maintenance_fee,
+                record.rate_per_unit,
+                record.total_amount
+            )
+        );
+        assert_eq!(read.zone, record.zone);
+    }
+
+    #[test]
+    fn csv_bytes_are_decoded_from_every_thai_encoding() {
+        // "ลำดับ" ในรหัส TIS-620 / Windows-874
+        let tis620 = [0xC5, 0xD3, 0xB4, 0xD1, 0xBA];
+        let utf8_bom = [&[0xEF, 0xBB, 0xBF][..], "ลำดับ".as_bytes()].concat();
+        let utf16_bom = [0xFF, 0xFE, b'a', 0, b',', 0, b'b', 0];
+        for (bytes, text) in [
+            (&utf8_bom[..], "ลำดับ"),
+            ("ลำดับ".as_bytes(), "ลำดับ"),
+            (&tis620[..], "ลำดับ"),
+            (&utf16_bom[..], "a,b"),
+            (&[][..], ""),
+        ] {
+            assert_eq!(decode_csv_bytes(bytes), text, "{:02X?}", bytes);
+        }
+    }
+
+    #[test]
+    fn delimiter_is_taken_from_the_header_line() {
+        for (text, delimiter) in [
+            ("ลำดับ,เลขมิเตอร์,ชื่อ\n1;2;3;4", b','),
+            ("ลำดับ;เลขมิเตอร์;ชื่อ\n1,2", b';'),
+            ("ลำดับ\tเลขมิเตอร์\tชื่อ\n1,2", b'\t'),
+            ("ลำดับ|เลขมิเตอร์|ชื่อ", b'|'),
+            ("\n\nลำดับ;เลขมิเตอร์", b';'),
+            ("\"ชื่อ, สกุล\";ลำดับ;เลขมิเตอร์", b';'),
+            ("ลำดับ", b','),
+            ("", b','),
+        ] {
+            assert_eq!(sniff_delimiter(text), delimiter, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn header_aliases_are_matched_without_case_or_extra_spaces() {
+        let aliases = HeaderAliases::default();
+        for (header, column) in [
+            ("Meter  No", "เลขมิเตอร์"),
+            (" ชื่อ-นามสกุล ", "ชื่อ"),
+            ("TOTAL", "จำนวน"),
+            ("ค่าบำรุง", "20"),
+            ("ลำดับ", "ลำดับ"),
+            (" ไม่รู้จัก ", "ไม่รู้จัก"),
+        ] {
+            assert_eq!(aliases.canonical(header), column, "{:?}", header);
+        }
+    }
+
+    #[test]
+    fn windows_874_semicolon_file_with_aliases_is_read() {
+        let text =
+            "order;meter;name;previous;current;maintenance;rate\n1;M001;สมชาย;1200;1250;20;2\n";
+        let (bytes, _, had_errors) = WINDOWS_874.encode(text);
+        assert!(!had_errors);
+        let path = std::env::temp_dir().join(format!(
+            "papa_moo_3_bills_windows_874_{}.csv",
+            std::process::id()
         ));
+        fs::write(&path, &bytes).unwrap();
+        let report = read_csv_file(&path.to_string_lossy(), &HeaderAliases::default()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        let record = &report.records[0];
+        assert_eq!(record.name, "สมชาย");
+        assert_eq!(record.previous_reading, Some(1200));
+        assert_eq!((record.units, record.total_amount), (50, 120));
     }
-    Ok(records)
 }
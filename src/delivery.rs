@@ -0,0 +1,169 @@
+use crate::error::Error;
+use crate::log;
+use crate::Result;
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::path::Path;
+
+const STATUS_HEADERS: [&str; 7] = [
+    "เวลา",
+    "ประจำเดือน",
+    "เลขที่บิล",
+    "เลขมิเตอร์",
+    "ผู้รับ",
+    "สถานะ",
+    "รายละเอียด",
+];
+// ค่าในคอลัมน์สถานะ ใช้ภาษาอังกฤษให้สคริปต์อ่านง่าย
+const STATUS_SENT: &str = "sent";
+const STATUS_FAILED: &str = "failed";
+
+/// ไฟล์สถานะการส่งบิล (CSV) เพิ่มบรรทัดต่อท้ายทุกครั้งที่ส่ง ใช้ร่วมกันทั้งอีเมลและการแจ้งบิล
+/// บิลที่เคยส่งถึงผู้รับเดิมสำเร็จแล้วจะไม่ส่งซ้ำ จึงรันซ้ำเพื่อส่งเฉพาะรายที่ไม่สำเร็จได้
+///
+/// บิลแยกกันด้วยประจำเดือนและเลขที่บิล เพราะเลขที่บิลมาจากเดือนที่ออกบิล
+/// บิลสองเดือนที่ออกในเดือนเดียวกันจึงได้เลขที่ซ้ำกันได้
+pub struct DeliveryLog {
+    path: String,
+    // (ประจำเดือน, เลขที่บิล, ผู้รับ)
+    sent: HashSet<(String, String, String)>,
+}
+
+impl DeliveryLog {
+    pub fn load(path: &str) -> Result<Self> {
+        let mut sent = HashSet::new();
+        if Path::new(path).exists() {
+            let mut reader = csv::ReaderBuilder::new().flexible(true).from_path(path)?;
+            for row in reader.records() {
+                let row = row?;
+                if row.get(5) == Some(STATUS_SENT) {
+                    let for_month = row.get(1).unwrap_or_default().to_string();
+                    let bill = row.get(2).unwrap_or_default().to_string();
+                    let recipient = row.get(4).unwrap_or_default().to_string();
+                    sent.insert((for_month, bill, recipient));
+                }
+            }
+            log::log_debug(&format!("เคยส่งสำเร็จแล้ว {} รายการ ({})", sent.len(), path));
+        }
+        Ok(DeliveryLog {
+            path: path.to_string(),
+            sent,
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// เคยส่งบิลนี้ของประจำเดือนนี้ถึงผู้รับนี้สำเร็จแล้วหรือไม่
+    pub fn already_sent(&self, for_month: &str, bill_number: &str, recipient: &str) -> bool {
+        self.sent.contains(&(
+            for_month.to_string(),
+            bill_number.to_string(),
+            recipient.to_string(),
+        ))
+    }
+
+    /// บันทึกผลการส่งหนึ่งรายการต่อท้ายไฟล์
+    pub fn record(
+        &mut self,
+        for_month: &str,
+        bill_number: &str,
+        meter_number: &str,
+        recipient: &str,
+        result: &Result<()>,
+    ) -> Result<()> {
+        let new_file = !Path::new(&self.path).exists();
+        if let Some(dir) = Path::new(&self.path).parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let mut writer = csv::Writer::from_writer(file);
+        if new_file {
+            writer.write_record(STATUS_HEADERS)?;
+        }
+        let (status, detail) = match result {
+            Ok(()) => (STATUS_SENT, String::new()),
+            Err(error) => (STATUS_FAILED, error.to_string()),
+        };
+        writer.write_record([
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            for_month.to_string(),
+            bill_number.to_string(),
+            meter_number.to_string(),
+            recipient.to_string(),
+            status.to_string(),
+            detail,
+        ])?;
+        writer.flush()?;
+        if result.is_ok() {
+            self.sent.insert((
+                for_month.to_string(),
+                bill_number.to_string(),
+                recipient.to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// ผลการส่งบิลทางอีเมลหรือการแจ้งบิล
+#[derive(Debug, Default)]
+pub struct DeliveryReport {
+    pub sent: usize,
+    pub failed: usize,
+    /// เคยส่งสำเร็จแล้ว หรือไม่มีผู้รับในรายชื่อ
+    pub skipped: usize,
+    /// รายการที่แสดงไว้ตอนทดลองส่ง (ไม่ได้ส่งจริง)
+    pub dry_run: usize,
+}
+
+impl DeliveryReport {
+    /// รวมผลการส่งอีกชุดเข้ามา
+    pub fn merge(&mut self, other: DeliveryReport) {
+        self.sent += other.sent;
+        self.failed += other.failed;
+        self.skipped += other.skipped;
+        self.dry_run += other.dry_run;
+    }
+
+    /// ข้อความ error เมื่อมีรายการที่ส่งไม่สำเร็จ (ให้โปรแกรมจบด้วยรหัสข้อผิดพลาดเครือข่าย)
+    /// `action` เช่น "ส่งอีเมล" หรือ "แจ้งบิล"
+    pub fn into_result(self, action: &str, status_path: &str) -> Result<()> {
+        if self.failed == 0 {
+            return Ok(());
+        }
+        Err(Error::Network(format!(
+            "{}ไม่สำเร็จ {} รายการ ดูรายละเอียดใน {} แล้วรันซ้ำเพื่อส่งเฉพาะรายการที่ไม่สำเร็จ",
+            action, self.failed, status_path
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_bill_number_in_another_period_is_not_already_sent() {
+        let path = std::env::temp_dir().join(format!("delivery_test_{}.csv", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = fs::remove_file(path);
+
+        let mut delivery_log = DeliveryLog::load(path).unwrap();
+        delivery_log
+            .record("ก.ย. 68", "6810-001", "M001", "a@example.com", &Ok(()))
+            .unwrap();
+        assert!(delivery_log.already_sent("ก.ย. 68", "6810-001", "a@example.com"));
+        assert!(!delivery_log.already_sent("ต.ค. 68", "6810-001", "a@example.com"));
+
+        // อ่านจากไฟล์แล้วได้ผลเดิม
+        let delivery_log = DeliveryLog::load(path).unwrap();
+        assert!(delivery_log.already_sent("ก.ย. 68", "6810-001", "a@example.com"));
+        assert!(!delivery_log.already_sent("ต.ค. 68", "6810-001", "a@example.com"));
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -1,4 +1,5 @@
 use crate::log;
+use printpdf::path::{PaintMode, WindingOrder};
 use printpdf::*;
 
 pub fn draw_bill_split_line() -> Line {
@@ -24,6 +25,28 @@ pub fn draw_line(y: Mm, start: Mm, end: Mm) -> Line {
     }
 }
 
+/// เส้นประแนวนอน คืนค่าเป็นเส้นสั้นๆ หลายเส้น
+pub fn draw_dashed_line(y: Mm, start: Mm, end: Mm, dash: Mm, gap: Mm) -> Vec<Line> {
+    log::log_debug(&format!(
+        "Drawing dashed line at y={}, from {} to {}",
+        y.0, start.0, end.0
+    ));
+    let mut segments = Vec::new();
+    let mut x = start;
+    while x < end {
+        let segment_end = if x + dash < end { x + dash } else { end };
+        segments.push(Line {
+            points: vec![
+                (Point::new(x, y), false),
+                (Point::new(segment_end, y), false),
+            ],
+            is_closed: false,
+        });
+        x = segment_end + gap;
+    }
+    segments
+}
+
 pub fn draw_vetical_line(x: Mm, start: Mm, end: Mm) -> Line {
     log::log_debug(&format!(
         "Drawing vertical line at x={}, from {} to {}",
@@ -34,3 +57,37 @@ pub fn draw_vetical_line(x: Mm, start: Mm, end: Mm) -> Line {
         is_closed: false,
     }
 }
+
+pub fn draw_rect(x: Mm, y: Mm, width: Mm, height: Mm) -> Line {
+    log::log_debug(&format!(
+        "Drawing rectangle at ({}, {}), size {} x {}",
+        x.0, y.0, width.0, height.0
+    ));
+    Line {
+        points: vec![
+            (Point::new(x, y), false),
+            (Point::new(x + width, y), false),
+            (Point::new(x + width, y + height), false),
+            (Point::new(x, y + height), false),
+        ],
+        is_closed: true,
+    }
+}
+
+/// สี่เหลี่ยมทึบ (ใช้สีพื้นที่ตั้งไว้ใน layer) สำหรับแท่งกราฟ
+pub fn draw_filled_rect(x: Mm, y: Mm, width: Mm, height: Mm) -> Polygon {
+    log::log_debug(&format!(
+        "Drawing filled rectangle at ({}, {}), size {} x {}",
+        x.0, y.0, width.0, height.0
+    ));
+    Polygon {
+        rings: vec![vec![
+            (Point::new(x, y), false),
+            (Point::new(x + width, y), false),
+            (Point::new(x + width, y + height), false),
+            (Point::new(x, y + height), false),
+        ]],
+        mode: PaintMode::Fill,
+        winding_order: WindingOrder::NonZero,
+    }
+}
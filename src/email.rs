@@ -0,0 +1,479 @@
+use crate::delivery::{DeliveryLog, DeliveryReport};
+use crate::error::Error;
+use crate::log;
+use crate::model::{bill_number, BillRecord};
+use crate::pdf_util::{create_pdf_bytes, BillOptions};
+use crate::thai_date::{format_date, MonthName};
+use crate::Result;
+use chrono::Days;
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+const DEFAULT_SUBJECT: &str = "ใบแจ้งค่าน้ำประปา หมู่ 3 ประจำเดือน {month}";
+const DEFAULT_BODY: &str = "เรียน {name}
+
+การประปาหมู่บ้าน หมู่ 3 ขอแจ้งค่าน้ำประปาประจำเดือน {month}
+เลขที่บิล {bill_number} เลขมิเตอร์ {meter}
+ใช้น้ำ {units} หน่วย ยอดที่ต้องชำระ {amount} บาท
+กรุณาชำระภายในวันที่ {due_date}
+
+ใบเสร็จแนบมาพร้อมอีเมลฉบับนี้
+";
+const SUMMARY_SUBJECT: &str = "สรุปการเก็บเงินค่าน้ำประปา หมู่ 3 ประจำเดือน {month}";
+const SUMMARY_BODY: &str = "รายงานสรุปการเก็บเงินค่าน้ำประปาประจำเดือน {month} แนบมาพร้อมอีเมลฉบับนี้\n";
+
+/// ชื่อคอลัมน์อีเมลในไฟล์รายชื่อ (อ่านด้วย csv_util::read_contacts)
+pub const CONTACT_COLUMNS: [&str; 3] = ["อีเมล", "email", "e-mail"];
+
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// การเข้ารหัสการเชื่อมต่อ SMTP
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum SmtpSecurity {
+    /// เริ่มแบบธรรมดาแล้วขอเข้ารหัส (พอร์ต 587)
+    #[default]
+    Starttls,
+    /// เข้ารหัสตั้งแต่เริ่มเชื่อมต่อ (พอร์ต 465)
+    Tls,
+    /// ไม่เข้ารหัส ใช้กับเซิร์ฟเวอร์ทดสอบในเครื่องเท่านั้น
+    None,
+}
+
+/// การตั้งค่าเซิร์ฟเวอร์ SMTP
+#[derive(Debug, Clone)]
+pub struct SmtpSettings {
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// ผู้ส่ง เช่น "ประปาหมู่ 3 <water@example.com>"
+    pub from: String,
+}
+
+/// หัวเรื่องและเนื้อความของอีเมล ใช้ {name} {meter} {month} {bill_number} {units} {amount} {due_date} แทนค่าของแต่ละบิล
+#[derive(Debug, Clone)]
+pub struct EmailTemplate {
+    pub subject: String,
+    pub body: String,
+}
+
+impl Default for EmailTemplate {
+    fn default() -> Self {
+        EmailTemplate {
+            subject: DEFAULT_SUBJECT.to_string(),
+            body: DEFAULT_BODY.to_string(),
+        }
+    }
+}
+
+impl EmailTemplate {
+    /// อ่านแม่แบบจากไฟล์ข้อความ: บรรทัดแรกเป็นหัวเรื่อง บรรทัดที่เหลือเป็นเนื้อความ
+    pub fn load(path: &str) -> Result<Self> {
+        log::log_info(&format!("กำลังอ่านแม่แบบอีเมล: {}", path));
+        let text = fs::read_to_string(path)?;
+        let text = text.trim_start_matches('\u{feff}');
+        let (subject, body) = text.split_once('\n').unwrap_or((text, ""));
+        if subject.trim().is_empty() {
+            return Err(Error::Validation(format!(
+                "บรรทัดแรกของแม่แบบอีเมล {} ต้องเป็นหัวเรื่อง",
+                path
+            )));
+        }
+        Ok(EmailTemplate {
+            subject: subject.trim().to_string(),
+            body: body.trim_start_matches(['\r', '\n']).to_string(),
+        })
+    }
+
+    fn render(&self, values: &[(&str, String)]) -> (String, String) {
+        let fill = |text: &str| {
+            values.iter().fold(text.to_string(), |text, (key, value)| {
+                text.replace(&format!("{{{}}}", key), value)
+            })
+        };
+        (fill(&self.subject), fill(&self.body))
+    }
+}
+
+/// ตัวส่งอีเมลผ่าน SMTP
+pub struct Mailer {
+    transport: SmtpTransport,
+    from: Mailbox,
+}
+
+impl Mailer {
+    pub fn new(settings: &SmtpSettings) -> Result<Self> {
+        let from = settings.from.parse::<Mailbox>().map_err(|error| {
+            Error::Validation(format!("อีเมลผู้ส่ง \"{}\" ไม่ถูกต้อง: {}", settings.from, error))
+        })?;
+        let smtp_error = |error: lettre::transport::smtp::Error| {
+            Error::Network(format!("เซิร์ฟเวอร์ SMTP {}: {}", settings.host, error))
+        };
+        let builder = match settings.security {
+            SmtpSecurity::Starttls => {
+                SmtpTransport::starttls_relay(&settings.host).map_err(smtp_error)?
+            }
+            SmtpSecurity::Tls => SmtpTransport::relay(&settings.host).map_err(smtp_error)?,
+            SmtpSecurity::None => SmtpTransport::builder_dangerous(&settings.host),
+        };
+        let mut builder = builder.port(settings.port).timeout(Some(SMTP_TIMEOUT));
+        if let Some(username) = &settings.username {
+            builder = builder.credentials(Credentials::new(
+                username.clone(),
+                settings.password.clone().unwrap_or_default(),
+            ));
+        }
+        log::log_info(&format!(
+            "ส่งอีเมลผ่าน {}:{} ({:?})",
+            settings.host, settings.port, settings.security
+        ));
+        Ok(Mailer {
+            transport: builder.build(),
+            from,
+        })
+    }
+
+    /// ส่งอีเมลหนึ่งฉบับพร้อมไฟล์ PDF แนบ
+    pub fn send(
+        &self,
+        to: &str,
+        (subject, body): (&str, &str),
+        (file_name, pdf): (&str, Vec<u8>),
+    ) -> Result<()> {
+        let to = to
+            .parse::<Mailbox>()
+            .map_err(|error| Error::Validation(format!("อีเมล \"{}\" ไม่ถูกต้อง: {}", to, error)))?;
+        let pdf_type = ContentType::parse("application/pdf")
+            .map_err(|error| Error::Validation(error.to_string()))?;
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(subject)
+            .multipart(
+                MultiPart::mixed()
+                    .singlepart(SinglePart::plain(body.to_string()))
+                    .singlepart(Attachment::new(file_name.to_string()).body(pdf, pdf_type)),
+            )
+            .map_err(|error| Error::Validation(format!("สร้างอีเมลไม่ได้: {}", error)))?;
+        self.transport
+            .send(&message)
+            .map_err(|error| Error::Network(format!("ส่งอีเมลไม่สำเร็จ: {}", error)))?;
+        Ok(())
+    }
+}
+
+/// ส่งใบเสร็จของแต่ละบิลไปยังอีเมลของมิเตอร์นั้น ส่งไม่สำเร็จรายใดจะบันทึกสถานะแล้วส่งรายต่อไป
+pub fn email_bills(
+    mailer: &Mailer,
+    records: &[BillRecord],
+    for_month: &str,
+    options: &BillOptions,
+    contacts: &HashMap<String, Vec<String>>,
+    template: &EmailTemplate,
+    delivery_log: &mut DeliveryLog,
+) -> Result<DeliveryReport> {
+    let mut report = DeliveryReport::default();
+    let due_date = options.issue_date + Days::new(options.due_days.into());
+    for record in records {
+        let Some(emails) = contacts.get(record.meter_number.trim()) else {
+            log::log_debug(&format!("ไม่มีอีเมลของมิเตอร์ {}", record.meter_number));
+            report.skipped += 1;
+            continue;
+        };
+        let number = bill_number(options.issue_date, record.order);
+        let (done, pending): (Vec<&String>, Vec<&String>) = emails
+            .iter()
+            .partition(|email| delivery_log.already_sent(for_month, &number, email));
+        for email in done {
+            log::log_info(&format!("บิล {} เคยส่งถึง {} แล้ว", number, email));
+            report.skipped += 1;
+        }
+        if pending.is_empty() {
+            continue;
+        }
+        let (subject, body) = template.render(&[
+            ("name", record.name.clone()),
+            ("meter", record.meter_number.clone()),
+            ("month", for_month.to_string()),
+            ("bill_number", number.clone()),
+            ("units", record.units.to_string()),
+            ("amount", record.total_amount.to_string()),
+            (
+                "due_date",
+                format_date(due_date, MonthName::Long, options.thai_digits),
+            ),
+        ]);
+        let pdf = create_pdf_bytes(std::slice::from_ref(record), for_month, options)?;
+        let file_name = format!("bill_{}.pdf", number);
+
+        for email in pending {
+            let result = mailer.send(email, (&subject, &body), (&file_name, pdf.clone()));
+            match &result {
+                Ok(()) => {
+                    log::log_info(&format!("ส่งบิล {} ถึง {} แล้ว", number, email));
+                    report.sent += 1;
+                }
+                Err(error) => {
+                    log::log_error(&format!("ส่งบิล {} ถึง {} ไม่สำเร็จ: {}", number, email, error));
+                    report.failed += 1;
+                }
+            }
+            delivery_log.record(for_month, &number, &record.meter_number, email, &result)?;
+        }
+    }
+    log::log_info(&format!(
+        "ส่งอีเมลสำเร็จ {} ฉบับ ไม่สำเร็จ {} ฉบับ ข้าม {} ฉบับ",
+        report.sent, report.failed, report.skipped
+    ));
+    Ok(report)
+}
+
+/// ส่งไฟล์ PDF สรุปการเก็บเงินประจำเดือน (เช่น ถึงสำนักงานอำเภอ)
+/// บันทึกผลใน `delivery_log` เหมือนบิล (เลขที่บิลเป็น "สรุป <เดือน>") ผู้รับที่เคยได้รับแล้วจะไม่ส่งซ้ำ
+/// ส่งไม่สำเร็จรายใดจะส่งรายต่อไป
+pub fn email_summary(
+    mailer: &Mailer,
+    recipients: &[String],
+    summary_path: &str,
+    for_month: &str,
+    delivery_log: &mut DeliveryLog,
+) -> Result<DeliveryReport> {
+    let mut report = DeliveryReport::default();
+    let key = format!("สรุป {}", for_month);
+    let pending: Vec<&String> = recipients
+        .iter()
+        .filter(|recipient| {
+            let done = delivery_log.already_sent(for_month, &key, recipient);
+            if done {
+                log::log_info(&format!("เคยส่งสรุปประจำเดือนถึง {} แล้ว", recipient));
+                report.skipped += 1;
+            }
+            !done
+        })
+        .collect();
+    if pending.is_empty() {
+        return Ok(report);
+    }
+    let pdf = fs::read(summary_path)?;
+    let file_name = Path::new(summary_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "summary.pdf".to_string());
+    let subject = SUMMARY_SUBJECT.replace("{month}", for_month);
+    let body = SUMMARY_BODY.replace("{month}", for_month);
+    for recipient in pending {
+        let result = mailer.send(recipient, (&subject, &body), (&file_name, pdf.clone()));
+        match &result {
+            Ok(()) => {
+                log::log_info(&format!("ส่งสรุปประจำเดือนถึง {} แล้ว", recipient));
+                report.sent += 1;
+            }
+            Err(error) => {
+                log::log_error(&format!(
+                    "ส่งสรุปประจำเดือนถึง {} ไม่สำเร็จ: {}",
+                    recipient, error
+                ));
+                report.failed += 1;
+            }
+        }
+        delivery_log.record(for_month, &key, "", recipient, &result)?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    // เซิร์ฟเวอร์ SMTP จำลอง รับทุกอีเมลยกเว้นผู้รับที่ขึ้นต้นด้วย bad เก็บผู้รับของอีเมลที่ส่งสำเร็จไว้
+    fn smtp_server() -> (u16, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let delivered = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&delivered);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                serve(stream.unwrap(), &sink);
+            }
+        });
+        (port, delivered)
+    }
+
+    fn serve(stream: TcpStream, delivered: &Mutex<Vec<String>>) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut stream = stream;
+        let mut recipient = String::new();
+        writeln!(stream, "220 localhost\r").unwrap();
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).unwrap() == 0 {
+                return;
+            }
+            let command = line.trim_end().to_ascii_uppercase();
+            let reply = if command.starts_with("EHLO") {
+                "250 localhost"
+            } else if command.starts_with("RCPT TO:<BAD") {
+                "550 no such user"
+            } else if command.starts_with("RCPT") {
+                recipient = line[9..].trim().trim_matches(['<', '>']).to_string();
+                "250 ok"
+            } else if command == "DATA" {
+                writeln!(stream, "354 go ahead\r").unwrap();
+                let mut data = String::new();
+                while data != ".\r\n" {
+                    data.clear();
+                    reader.read_line(&mut data).unwrap();
+                }
+                delivered.lock().unwrap().push(recipient.clone());
+                "250 queued"
+            } else if command == "QUIT" {
+                writeln!(stream, "221 bye\r").unwrap();
+                return;
+            } else {
+                "250 ok"
+            };
+            writeln!(stream, "{}\r", reply).unwrap();
+        }
+    }
+
+    fn mailer(port: u16) -> Mailer {
+        Mailer::new(&SmtpSettings {
+            host: "127.0.0.1".to_string(),
+            port,
+            security: SmtpSecurity::None,
+            username: None,
+            password: None,
+            from: "ประปาหมู่ 3 <water@example.com>".to_string(),
+        })
+        .unwrap()
+    }
+
+    fn status_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!(
+            "papa_moo_3_email_{}_{}.csv",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_file(&path);
+        path.display().to_string()
+    }
+
+    #[test]
+    fn summary_continues_after_failure_and_is_not_resent() {
+        let (port, delivered) = smtp_server();
+        let mailer = mailer(port);
+        let summary =
+            std::env::temp_dir().join(format!("papa_moo_3_summary_{}.pdf", std::process::id()));
+        fs::write(&summary, b"%PDF-1.4").unwrap();
+        let summary = summary.display().to_string();
+        let recipients = vec![
+            "bad@example.com".to_string(),
+            "office@example.com".to_string(),
+        ];
+        let mut delivery_log = DeliveryLog::load(&status_path("summary")).unwrap();
+
+        let report = email_summary(
+            &mailer,
+            &recipients,
+            &summary,
+            "ตุลาคม 2568",
+            &mut delivery_log,
+        )
+        .unwrap();
+        assert_eq!((report.sent, report.failed), (1, 1));
+        assert_eq!(*delivered.lock().unwrap(), ["office@example.com"]);
+
+        // รันซ้ำ: ส่งเฉพาะรายที่ยังไม่สำเร็จ
+        let report = email_summary(
+            &mailer,
+            &recipients,
+            &summary,
+            "ตุลาคม 2568",
+            &mut delivery_log,
+        )
+        .unwrap();
+        assert_eq!((report.sent, report.failed, report.skipped), (0, 1, 1));
+        assert_eq!(delivered.lock().unwrap().len(), 1);
+
+        // เดือนใหม่ส่งได้อีก
+        let report = email_summary(
+            &mailer,
+            &recipients,
+            &summary,
+            "พฤศจิกายน 2568",
+            &mut delivery_log,
+        )
+        .unwrap();
+        assert_eq!(report.sent, 1);
+    }
+
+    #[test]
+    fn bills_are_sent_once_per_address() {
+        let (port, delivered) = smtp_server();
+        let mailer = mailer(port);
+        let record = BillRecord {
+            order: 1,
+            meter_number: "M001".to_string(),
+            name: "สมชาย".to_string(),
+            previous_reading: Some(100),
+            current_reading: 130,
+            units: 30,
+            maintenance_fee: 20,
+            rate_per_unit: 2,
+            total_amount: 80,
+            zone: String::new(),
+        };
+        let contacts = HashMap::from([(
+            "M001".to_string(),
+            vec![
+                "owner@example.com".to_string(),
+                "bad@example.com".to_string(),
+            ],
+        )]);
+        let mut delivery_log = DeliveryLog::load(&status_path("bills")).unwrap();
+        let send = |delivery_log: &mut DeliveryLog| {
+            email_bills(
+                &mailer,
+                std::slice::from_ref(&record),
+                "ต.ค. 68",
+                &BillOptions::default(),
+                &contacts,
+                &EmailTemplate::default(),
+                delivery_log,
+            )
+            .unwrap()
+        };
+
+        let report = send(&mut delivery_log);
+        assert_eq!((report.sent, report.failed), (1, 1));
+        let report = send(&mut delivery_log);
+        assert_eq!((report.sent, report.failed, report.skipped), (0, 1, 1));
+        assert_eq!(*delivered.lock().unwrap(), ["owner@example.com"]);
+    }
+
+    #[test]
+    fn template_fills_placeholders() {
+        let template = EmailTemplate {
+            subject: "บิล {month}".to_string(),
+            body: "เรียน {name} ยอด {amount} บาท".to_string(),
+        };
+        let (subject, body) = template.render(&[
+            ("month", "ต.ค. 68".to_string()),
+            ("name", "สมชาย".to_string()),
+            ("amount", "80".to_string()),
+        ]);
+        assert_eq!(subject, "บิล ต.ค. 68");
+        assert_eq!(body, "เรียน สมชาย ยอด 80 บาท");
+    }
+}
@@ -0,0 +1,158 @@
+use std::fmt;
+use std::io;
+
+/// ข้อผิดพลาดของโปรแกรม แยกตามประเภท เพื่อให้สคริปต์หรือโปรแกรมอื่นรู้ว่าผิดพลาดที่ขั้นตอนไหน
+#[derive(Debug)]
+pub enum Error {
+    /// อ่านหรือเขียนไฟล์ CSV ไม่ได้
+    Csv(csv::Error),
+    /// อ่านไฟล์ Excel/ODS ไม่ได้
+    Sheet(calamine::Error),
+    /// อ่านหรือเขียนไฟล์ JSON ไม่ได้
+    Json(serde_json::Error),
+    /// ข้อมูลหรือตัวเลือกไม่ถูกต้อง เช่น แถวข้อมูลผิดพลาด ไม่พบชีต ไม่พบบิลที่ระบุ
+    Validation(String),
+    /// โหลดฟอนต์ไม่ได้
+    Font(String),
+    /// สร้างไฟล์ PDF ไม่ได้
+    Pdf(String),
+    /// เขียนไฟล์ Excel ไม่ได้
+    Xlsx(rust_xlsxwriter::XlsxError),
+    /// อ่านหรือเขียนไฟล์ไม่ได้
+    Io(io::Error),
+    /// ดาวน์โหลดข้อมูลจากอินเทอร์เน็ตไม่ได้
+    Network(String),
+    /// ส่งงานพิมพ์ไปยังเครื่องพิมพ์ไม่ได้
+    Print(String),
+}
+
+impl Error {
+    /// รหัสจบการทำงานของโปรแกรม (exit code) แยกตามประเภทข้อผิดพลาด
+    /// 1 ไม่ได้ใช้ และ 2 เป็นรหัสของตัวเลือกบรรทัดคำสั่งผิด
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::Io(_) => 3,
+            Error::Csv(_) => 4,
+            Error::Sheet(_) => 5,
+            Error::Json(_) => 6,
+            Error::Validation(_) => 7,
+            Error::Font(_) => 8,
+            Error::Pdf(_) => 9,
+            Error::Xlsx(_) => 10,
+            Error::Network(_) => 11,
+            Error::Print(_) => 12,
+        }
+    }
+
+    /// คำอธิบายประเภทข้อผิดพลาดภาษาไทย
+    pub fn message_th(&self) -> &'static str {
+        match self {
+            Error::Io(_) => "อ่านหรือเขียนไฟล์ไม่สำเร็จ",
+            Error::Csv(_) => "อ่านหรือเขียนไฟล์ CSV ไม่สำเร็จ",
+            Error::Sheet(_) => "อ่านไฟล์ Excel/ODS ไม่สำเร็จ",
+            Error::Json(_) => "อ่านหรือเขียนไฟล์ JSON ไม่สำเร็จ",
+            Error::Validation(_) => "ข้อมูลไม่ถูกต้อง",
+            Error::Font(_) => "โหลดฟอนต์ไม่สำเร็จ",
+            Error::Pdf(_) => "สร้างไฟล์ PDF ไม่สำเร็จ",
+            Error::Xlsx(_) => "เขียนไฟล์ Excel ไม่สำเร็จ",
+            Error::Network(_) => "เชื่อมต่ออินเทอร์เน็ตไม่สำเร็จ",
+            Error::Print(_) => "ส่งงานพิมพ์ไม่สำเร็จ",
+        }
+    }
+
+    /// คำอธิบายประเภทข้อผิดพลาดภาษาอังกฤษ
+    pub fn message_en(&self) -> &'static str {
+        match self {
+            Error::Io(_) => "file I/O error",
+            Error::Csv(_) => "CSV error",
+            Error::Sheet(_) => "spreadsheet error",
+            Error::Json(_) => "JSON error",
+            Error::Validation(_) => "invalid data",
+            Error::Font(_) => "font error",
+            Error::Pdf(_) => "PDF error",
+            Error::Xlsx(_) => "Excel writer error",
+            Error::Network(_) => "network error",
+            Error::Print(_) => "printing error",
+        }
+    }
+}
+
+/// แสดงเป็น "คำอธิบายภาษาไทย (English): รายละเอียด"
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({}): ", self.message_th(), self.message_en())?;
+        match self {
+            Error::Io(error) => write!(f, "{}", error),
+            Error::Csv(error) => write!(f, "{}", error),
+            Error::Sheet(error) => write!(f, "{}", error),
+            Error::Json(error) => write!(f, "{}", error),
+            Error::Xlsx(error) => write!(f, "{}", error),
+            Error::Validation(detail)
+            | Error::Font(detail)
+            | Error::Pdf(detail)
+            | Error::Network(detail)
+            | Error::Print(detail) => write!(f, "{}", detail),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            Error::Csv(error) => Some(error),
+            Error::Sheet(error) => Some(error),
+            Error::Json(error) => Some(error),
+            Error::Xlsx(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(error: csv::Error) -> Self {
+        Error::Csv(error)
+    }
+}
+
+impl From<calamine::Error> for Error {
+    fn from(error: calamine::Error) -> Self {
+        Error::Sheet(error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::Json(error)
+    }
+}
+
+impl From<rust_xlsxwriter::XlsxError> for Error {
+    fn from(error: rust_xlsxwriter::XlsxError) -> Self {
+        Error::Xlsx(error)
+    }
+}
+
+impl From<printpdf::Error> for Error {
+    fn from(error: printpdf::Error) -> Self {
+        Error::Pdf(error.to_string())
+    }
+}
+
+impl From<printpdf::lopdf::Error> for Error {
+    fn from(error: printpdf::lopdf::Error) -> Self {
+        Error::Pdf(error.to_string())
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(error: reqwest::Error) -> Self {
+        Error::Network(error.to_string())
+    }
+}
@@ -1,22 +1,37 @@
+use crate::error::Error;
 use crate::log;
+use crate::Result;
 // use font_kit::font;
 use printpdf::*;
 use reqwest::blocking::Client;
-use std::error::Error;
 use std::fs::{self, File};
 use std::path::{Path, PathBuf};
 
-pub fn get_fonts_dir() -> PathBuf {
+pub fn get_fonts_dir() -> Result<PathBuf> {
     let mut fonts_dir = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
     fonts_dir.push("papa_moo_3_bills");
     fonts_dir.push("fonts");
-    fs::create_dir_all(&fonts_dir).expect("Failed to create fonts directory");
+    fs::create_dir_all(&fonts_dir).map_err(|error| {
+        Error::Font(format!(
+            "สร้างโฟลเดอร์ฟอนต์ {} ไม่ได้: {}",
+            fonts_dir.display(),
+            error
+        ))
+    })?;
     log::log_debug(&format!("Font directory: {:?}", fonts_dir));
-    fonts_dir
+    Ok(fonts_dir)
 }
 
-pub fn download_font(font_name: &str, style: &str) -> Result<PathBuf, Box<dyn Error>> {
-    let fonts_dir = get_fonts_dir();
+// เพิ่มไฟล์ฟอนต์ลงในเอกสาร ถ้าเปิดหรืออ่านไฟล์ไม่ได้จะบอกชื่อไฟล์ใน error
+fn add_font_file(doc: &PdfDocumentReference, path: &Path) -> Result<IndirectFontRef> {
+    let file = File::open(path)
+        .map_err(|error| Error::Font(format!("เปิดไฟล์ฟอนต์ {} ไม่ได้: {}", path.display(), error)))?;
+    doc.add_external_font(file)
+        .map_err(|error| Error::Font(format!("อ่านไฟล์ฟอนต์ {} ไม่ได้: {}", path.display(), error)))
+}
+
+pub fn download_font(font_name: &str, style: &str) -> Result<PathBuf> {
+    let fonts_dir = get_fonts_dir()?;
     let font_path = fonts_dir.join(format!("{}-{}.ttf", font_name, style));
 
     // ถ้ามีไฟล์อยู่แล้ว ไม่ต้องดาวน์โหลดใหม่
@@ -47,7 +62,7 @@ pub fn download_font(font_name: &str, style: &str) -> Result<PathBuf, Box<dyn Er
                 .next()
                 .map(|s| s.trim_matches(|c| c == '\'' || c == '"'))
         })
-        .ok_or_else(|| "Failed to extract font URL from CSS")?;
+        .ok_or_else(|| Error::Network("Failed to extract font URL from CSS".to_string()))?;
 
     // ดาวน์โหลดไฟล์ฟอนต์
     log::log_debug(&format!("Downloading font from URL: {}", font_url));
@@ -60,7 +75,7 @@ pub fn download_font(font_name: &str, style: &str) -> Result<PathBuf, Box<dyn Er
 
 pub fn find_thai_font(
     doc: &PdfDocumentReference,
-) -> (IndirectFontRef, IndirectFontRef, IndirectFontRef) {
+) -> Result<(IndirectFontRef, IndirectFontRef, IndirectFontRef)> {
     // ลองดาวน์โหลดฟอนต์จาก Google Fonts ก่อน
     let font_candidates = ["Sarabun", "Noto Sans Thai", "Prompt", "Kanit"];
     log::log_info("กำลังค้นหา font ภาษาไทย จาก Google Fonts...");
@@ -72,16 +87,10 @@ pub fn find_thai_font(
         ) {
             (Ok(regular_path), Ok(bold_path)) => {
                 log::log_info(&format!("ใช้ font {} จาก Google Fonts", font_name));
-                let regular_font = doc
-                    .add_external_font(File::open(&regular_path).unwrap())
-                    .unwrap();
-                let bold_font = doc
-                    .add_external_font(File::open(&bold_path).unwrap())
-                    .unwrap();
-                let italic_font = doc
-                    .add_external_font(File::open(&regular_path).unwrap())
-                    .unwrap();
-                return (regular_font, bold_font, italic_font);
+                let regular_font = add_font_file(doc, &regular_path)?;
+                let bold_font = add_font_file(doc, &bold_path)?;
+                let italic_font = add_font_file(doc, &regular_path)?;
+                return Ok((regular_font, bold_font, italic_font));
             }
             _ => continue,
         }
@@ -136,23 +145,21 @@ pub fn find_thai_font(
             && Path::new(italic_path).exists()
         {
             log::log_info(&format!("Using system font {} ({})", name, regular_path));
-            let regular_font = doc
-                .add_external_font(File::open(regular_path).unwrap())
-                .unwrap();
-            let bold_font = doc
-                .add_external_font(File::open(bold_path).unwrap())
-                .unwrap();
-            let italic_font = doc
-                .add_external_font(File::open(italic_path).unwrap())
-                .unwrap();
-            return (regular_font, bold_font, italic_font);
+            let regular_font = add_font_file(doc, Path::new(regular_path))?;
+            let bold_font = add_font_file(doc, Path::new(bold_path))?;
+            let italic_font = add_font_file(doc, Path::new(italic_path))?;
+            return Ok((regular_font, bold_font, italic_font));
         }
     }
 
     // Fallback to system fonts if no Thai fonts are found
     log::log_warn("No Thai fonts found, using Helvetica as fallback");
-    let regular_font = doc.add_builtin_font(BuiltinFont::Helvetica).unwrap();
-    let bold_font = doc.add_builtin_font(BuiltinFont::HelveticaBold).unwrap();
-    let italic_font = doc.add_builtin_font(BuiltinFont::Helvetica).unwrap();
-    (regular_font, bold_font, italic_font)
+    let add_builtin_font = |font| {
+        doc.add_builtin_font(font)
+            .map_err(|error| Error::Font(error.to_string()))
+    };
+    let regular_font = add_builtin_font(BuiltinFont::Helvetica)?;
+    let bold_font = add_builtin_font(BuiltinFont::HelveticaBold)?;
+    let italic_font = add_builtin_font(BuiltinFont::Helvetica)?;
+    Ok((regular_font, bold_font, italic_font))
 }
@@ -0,0 +1,222 @@
+use crate::log;
+use crate::model::BillRecord;
+use crate::thai_date::YearMonth;
+use crate::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HistoryEntry {
+    /// เดือนที่ออกบิลแบบ YYYY-MM
+    #[serde(rename = "เดือน")]
+    pub month: String,
+    #[serde(rename = "เลขมิเตอร์")]
+    pub meter_number: String,
+    #[serde(rename = "ชื่อ")]
+    pub name: String,
+    #[serde(rename = "เลขอ่านครั้งก่อน")]
+    pub previous_reading: u32,
+    #[serde(rename = "เลขอ่านครั้งหลัง")]
+    pub current_reading: u32,
+    #[serde(rename = "หน่วย")]
+    pub units: u32,
+}
+
+/// เลขอ่านมิเตอร์ที่ไม่ตรงกับเลขอ่านครั้งหลังของเดือนก่อน
+#[derive(Debug)]
+pub struct ReadingMismatch {
+    pub order: u32,
+    pub meter_number: String,
+    pub name: String,
+    pub supplied: u32,
+    pub stored: u32,
+}
+
+/// ประวัติการอ่านมิเตอร์ทุกเดือน เก็บเป็นไฟล์ CSV เรียงตามลำดับเดือนที่ออกบิล
+pub struct HistoryStore {
+    entries: Vec<HistoryEntry>,
+}
+
+impl HistoryStore {
+    pub fn load(path: &str) -> Result<Self> {
+        if !Path::new(path).exists() {
+            log::log_info(&format!("ยังไม่มีไฟล์ประวัติ: {}", path));
+            return Ok(HistoryStore {
+                entries: Vec::new(),
+            });
+        }
+
+        log::log_info(&format!("กำลังอ่านไฟล์ประวัติ: {}", path));
+        let mut reader = csv::Reader::from_path(path)?;
+        let entries: Vec<HistoryEntry> = reader.deserialize().collect::<Result<_, _>>()?;
+        log::log_debug(&format!("ประวัติทั้งหมด {} รายการ", entries.len()));
+        Ok(HistoryStore { entries })
+    }
+
+    /// ประวัติก่อนเดือนที่ระบุ (ข้ามเดือนนี้และเดือนถัดไปที่บันทึกไว้จากการรันครั้งก่อน)
+    /// เดือนที่อ่านไม่ออก (ไฟล์ประวัติรุ่นเก่าที่เก็บเป็นชื่อเดือน) ถือว่าอยู่ก่อนเสมอ
+    fn entries_before(&self, month: YearMonth) -> impl Iterator<Item = &HistoryEntry> {
+        self.entries.iter().filter(move |entry| {
+            entry
+                .month
+                .parse::<YearMonth>()
+                .map_or(true, |entry_month| entry_month < month)
+        })
+    }
+
+    /// หน่วยที่ใช้ของแต่ละมิเตอร์ในแต่ละเดือน (เฉพาะเดือนที่อ่านออกเป็น YYYY-MM)
+    pub fn monthly_units(&self) -> impl Iterator<Item = (YearMonth, &str, u32)> {
+        self.entries.iter().filter_map(|entry| {
+            let month = entry.month.parse::<YearMonth>().ok()?;
+            Some((month, entry.meter_number.as_str(), entry.units))
+        })
+    }
+
+    /// เลขอ่านครั้งหลังล่าสุดของแต่ละมิเตอร์ก่อนเดือนที่ระบุ
+    pub fn last_readings(&self, month: YearMonth) -> HashMap<String, u32> {
+        self.entries_before(month)
+            .map(|entry| (entry.meter_number.trim().to_string(), entry.current_reading))
+            .collect()
+    }
+
+    /// บันทึกการอ่านมิเตอร์ของเดือนนี้ ถ้ามีเดือนเดียวกันอยู่แล้วจะเขียนทับ
+    pub fn record_month(&mut self, month: YearMonth, records: &[BillRecord]) {
+        let key = month.to_string();
+        self.entries.retain(|entry| entry.month != key);
+        self.entries
+            .extend(records.iter().map(|record| HistoryEntry {
+                month: key.clone(),
+                meter_number: record.meter_number.clone(),
+                name: record.name.clone(),
+                previous_reading: record.previous(),
+                current_reading: record.current_reading,
+                units: record.units,
+            }));
+        // เรียงตามเดือน เผื่อออกบิลย้อนหลัง (sort แบบคงลำดับเดิมภายในเดือนเดียวกัน)
+        self.entries
+            .sort_by_key(|entry| entry.month.parse::<YearMonth>().ok());
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        if let Some(dir) = Path::new(path).parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut writer = csv::Writer::from_path(path)?;
+        for entry in &self.entries {
+            writer.serialize(entry)?;
+        }
+        writer.flush()?;
+        log::log_info(&format!("บันทึกประวัติการอ่านมิเตอร์: {}", path));
+        Ok(())
+    }
+}
+
+/// เลขอ่านครั้งหลังของแต่ละมิเตอร์จากไฟล์ CSV ของเดือนก่อน
+pub fn last_readings_from_records(records: &[BillRecord]) -> HashMap<String, u32> {
+    records
+        .iter()
+        .map(|record| {
+            (
+                record.meter_number.trim().to_string(),
+                record.current_reading,
+            )
+        })
+        .collect()
+}
+
+/// เติมเลขอ่านครั้งก่อนที่เว้นว่างไว้ และคืนรายการที่เลขอ่านครั้งก่อนไม่ตรงกับเดือนก่อน
+/// หน่วยและยอดเงินคำนวณภายหลังด้วย `BillRecord::complete`
+pub fn carry_forward(
+    records: &mut [BillRecord],
+    last_readings: &HashMap<String, u32>,
+) -> Vec<ReadingMismatch> {
+    let mut mismatches = Vec::new();
+
+    for record in records.iter_mut() {
+        let Some(&stored) = last_readings.get(record.meter_number.trim()) else {
+            continue;
+        };
+
+        match record.previous_reading {
+            None => {
+                record.previous_reading = Some(stored);
+                log::log_debug(&format!(
+                    "เติมเลขอ่านครั้งก่อน {}: {}",
+                    record.meter_number, stored
+                ));
+            }
+            Some(supplied) if supplied != stored => {
+                log::log_debug(&format!(
+                    "มิเตอร์ {} ({}) เลขอ่านครั้งก่อน {} ไม่ตรงกับเดือนก่อน {}",
+                    record.meter_number, record.name, supplied, stored
+                ));
+                mismatches.push(ReadingMismatch {
+                    order: record.order,
+                    meter_number: record.meter_number.clone(),
+                    name: record.name.clone(),
+                    supplied,
+                    stored,
+                });
+            }
+            Some(_) => {}
+        }
+    }
+
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(
+        meter_number: &str,
+        previous_reading: Option<u32>,
+        current_reading: u32,
+    ) -> BillRecord {
+        BillRecord {
+            order: 1,
+            meter_number: meter_number.to_string(),
+            name: "นายสมชาย".to_string(),
+            previous_reading,
+            current_reading,
+            units: 0,
+            maintenance_fee: 20,
+            rate_per_unit: 2,
+            total_amount: 0,
+            zone: String::new(),
+        }
+    }
+
+    #[test]
+    fn carry_forward_fills_blank_readings_with_trimmed_meter_numbers() {
+        let last_readings = last_readings_from_records(&[record(" M001 ", Some(1100), 1200)]);
+        let mut records = vec![
+            record("M001", None, 1250),
+            record("M002", None, 830),
+            record("M001 ", Some(0), 40),
+        ];
+        let mismatches = carry_forward(&mut records, &last_readings);
+
+        assert_eq!(records[0].previous_reading, Some(1200));
+        // ไม่มีเดือนก่อน คงว่างไว้ให้ prepare_bills ปฏิเสธ
+        assert_eq!(records[1].previous_reading, None);
+        // เลขอ่าน 0 ที่กรอกมาใช้ตามเดิม แต่รายงานว่าไม่ตรงกับเดือนก่อน
+        assert_eq!(records[2].previous_reading, Some(0));
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!((mismatches[0].supplied, mismatches[0].stored), (0, 1200));
+    }
+
+    #[test]
+    fn history_readings_are_keyed_by_trimmed_meter_number() {
+        let mut history = HistoryStore {
+            entries: Vec::new(),
+        };
+        let september: YearMonth = "2025-09".parse().unwrap();
+        history.record_month(september, &[record(" M001", Some(1100), 1200)]);
+        let last_readings = history.last_readings(september.add_months(1));
+        assert_eq!(last_readings.get("M001"), Some(&1200));
+    }
+}
@@ -0,0 +1,223 @@
+use crate::csv_util::read_csv_file;
+use crate::error::Error;
+use crate::json_util::read_json_file;
+use crate::log;
+use crate::model::BillRecord;
+use crate::sheet_util::read_sheet_file;
+use crate::Result;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+// ชื่อคอลัมน์อื่นที่พบบ่อย -> ชื่อคอลัมน์ที่ BillRecord ใช้ (ดู model.rs)
+const DEFAULT_HEADER_ALIASES: [(&str, &str); 33] = [
+    ("ลำดับที่", "ลำดับ"),
+    ("order", "ลำดับ"),
+    ("no", "ลำดับ"),
+    ("no.", "ลำดับ"),
+    ("หมายเลขมิเตอร์", "เลขมิเตอร์"),
+    ("meter", "เลขมิเตอร์"),
+    ("meter_number", "เลขมิเตอร์"),
+    ("meter no", "เลขมิเตอร์"),
+    ("ชื่อ-นามสกุล", "ชื่อ"),
+    ("ชื่อ-สกุล", "ชื่อ"),
+    ("name", "ชื่อ"),
+    ("previous", "เลขอ่านครั้งก่อน"),
+    ("previous_reading", "เลขอ่านครั้งก่อน"),
+    ("current", "เลขอ่านครั้งหลัง"),
+    ("current_reading", "เลขอ่านครั้งหลัง"),
+    ("จำนวนหน่วย", "หน่วย"),
+    ("units", "หน่วย"),
+    ("ค่าบำรุง", "20"),
+    ("ค่าบำรุงรักษา", "20"),
+    ("maintenance", "20"),
+    ("maintenance_fee", "20"),
+    ("หน่วยละ", "หนวยละ"),
+    ("rate", "หนวยละ"),
+    ("rate_per_unit", "หนวยละ"),
+    ("จำนวนเงิน", "จำนวน"),
+    ("รวม", "จำนวน"),
+    ("amount", "จำนวน"),
+    ("total", "จำนวน"),
+    ("total_amount", "จำนวน"),
+    ("zone", "โซน"),
+    ("route", "โซน"),
+    ("เส้นทาง", "โซน"),
+    ("หมู่", "โซน"),
+];
+
+/// ตารางแปลงชื่อหัวคอลัมน์ให้เป็นชื่อที่โปรแกรมใช้
+#[derive(Debug, Clone)]
+pub struct HeaderAliases {
+    aliases: HashMap<String, String>,
+}
+
+impl Default for HeaderAliases {
+    fn default() -> Self {
+        let aliases = DEFAULT_HEADER_ALIASES
+            .iter()
+            .map(|(alias, column)| (normalize_header(alias), column.to_string()))
+            .collect();
+        HeaderAliases { aliases }
+    }
+}
+
+impl HeaderAliases {
+    /// เพิ่มชื่อคอลัมน์จากไฟล์ CSV สองคอลัมน์: ชื่อในไฟล์, ชื่อคอลัมน์ของโปรแกรม
+    pub fn load_file(&mut self, file_path: &str) -> Result<()> {
+        log::log_info(&format!("กำลังอ่านไฟล์ชื่อคอลัมน์: {}", file_path));
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .trim(csv::Trim::All)
+            .from_path(file_path)?;
+        for row in reader.records() {
+            let row = row?;
+            if let (Some(alias), Some(column)) = (row.get(0), row.get(1)) {
+                log::log_debug(&format!("ชื่อคอลัมน์ {} -> {}", alias, column));
+                self.aliases
+                    .insert(normalize_header(alias), column.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// ชื่อคอลัมน์ที่โปรแกรมใช้ ถ้าไม่รู้จักจะคืนชื่อเดิม (ตัดช่องว่างแล้ว)
+    pub fn canonical(&self, header: &str) -> String {
+        let header = header.trim();
+        self.aliases
+            .get(&normalize_header(header))
+            .cloned()
+            .unwrap_or_else(|| header.to_string())
+    }
+}
+
+fn normalize_header(header: &str) -> String {
+    header
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// แถวข้อมูลที่อ่านไม่ได้
+#[derive(Debug, Clone)]
+pub struct RowError {
+    /// เลขบรรทัดในไฟล์ (เลขแถวในชีต หรือลำดับรายการใน JSON array)
+    pub line: u64,
+    pub column: Option<String>,
+    pub value: Option<String>,
+    pub message: String,
+    /// ค่าทุกช่องในแถว ใช้เขียนไฟล์แถวที่ถูกปฏิเสธ
+    pub raw: Vec<String>,
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "บรรทัด {}", self.line)?;
+        if let Some(column) = &self.column {
+            write!(f, " คอลัมน์ \"{}\"", column)?;
+        }
+        if let Some(value) = &self.value {
+            write!(f, " ค่า \"{}\"", value)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// ผลการอ่านไฟล์ข้อมูล: รายการที่อ่านได้ และแถวที่ผิดพลาดทั้งหมด
+#[derive(Debug, Default)]
+pub struct ReadReport {
+    pub headers: Vec<String>,
+    pub records: Vec<BillRecord>,
+    pub errors: Vec<RowError>,
+}
+
+impl ReadReport {
+    pub fn log_errors(&self, file_path: &str) {
+        if self.errors.is_empty() {
+            return;
+        }
+        log::log_error(&format!(
+            "พบข้อมูลผิดพลาด {} แถวในไฟล์ {}:",
+            self.errors.len(),
+            file_path
+        ));
+        for error in &self.errors {
+            log::log_error(&format!("  {}", error));
+        }
+    }
+
+    /// เขียนแถวที่ผิดพลาดลงไฟล์ CSV พร้อมคอลัมน์สาเหตุ เพื่อแก้ไขแล้วนำเข้าใหม่
+    pub fn write_rejected(&self, output_path: &str) -> Result<()> {
+        if let Some(dir) = Path::new(output_path).parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut writer = csv::WriterBuilder::new()
+            .flexible(true)
+            .from_path(output_path)?;
+        let mut headers = self.headers.clone();
+        headers.push("ข้อผิดพลาด".to_string());
+        writer.write_record(&headers)?;
+        for error in &self.errors {
+            let mut row = error.raw.clone();
+            row.resize(self.headers.len(), String::new());
+            row.push(error.to_string());
+            writer.write_record(&row)?;
+        }
+        writer.flush()?;
+        log::log_warn(&format!(
+            "บันทึกแถวที่ผิดพลาด {} แถวไว้ที่ {}",
+            self.errors.len(),
+            output_path
+        ));
+        Ok(())
+    }
+
+    /// คืนรายการที่อ่านได้ ถ้ามีแถวผิดพลาดและไม่ได้เลือกข้ามแถวผิดพลาดจะคืน error
+    pub fn into_records(self, skip_invalid: bool) -> Result<Vec<BillRecord>> {
+        if !self.errors.is_empty() && !skip_invalid {
+            return Err(Error::Validation(format!(
+                "ข้อมูลผิดพลาด {} แถว (แถวแรก: {})",
+                self.errors.len(),
+                self.errors[0]
+            )));
+        }
+        Ok(self.records)
+    }
+}
+
+/// คำนวณหน่วยและยอดเงินของแถวที่กรอกเลขอ่านครั้งก่อนมาแล้ว (ดู `BillRecord::complete`)
+/// แถวที่เว้นเลขอ่านครั้งก่อนไว้ คำนวณหลังยกเลขอ่านจากเดือนก่อนใน `billing::prepare_bills`
+pub(crate) fn complete_record(record: &mut BillRecord) -> std::result::Result<(), String> {
+    match record.previous_reading {
+        Some(_) => record.complete(),
+        None => Ok(()),
+    }
+}
+
+/// ตัวเลือกการอ่านไฟล์ข้อมูล
+#[derive(Debug, Clone, Default)]
+pub struct InputOptions {
+    /// ชื่อชีตในไฟล์ Excel/ODS (ค่าเริ่มต้นคือชีตแรก)
+    pub sheet: Option<String>,
+    pub aliases: HeaderAliases,
+}
+
+/// อ่านข้อมูลบิลตามนามสกุลไฟล์: .xlsx/.xlsm/.xls/.ods อ่านเป็นตาราง, .json/.jsonl อ่านเป็น JSON
+/// นอกนั้นอ่านเป็น CSV
+/// แถวที่ผิดพลาดจะถูกรวบรวมไว้ใน `ReadReport::errors` แทนการหยุดที่แถวแรก
+pub fn read_records(file_path: &str, options: &InputOptions) -> Result<ReadReport> {
+    let extension = Path::new(file_path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "xlsx" | "xlsm" | "xls" | "ods" => {
+            read_sheet_file(file_path, options.sheet.as_deref(), &options.aliases)
+        }
+        "json" | "jsonl" | "ndjson" => read_json_file(file_path),
+        _ => read_csv_file(file_path, &options.aliases),
+    }
+}
@@ -0,0 +1,186 @@
+use crate::log;
+use crate::model::{bill_number, BillRecord};
+use crate::pdf_util::BillOptions;
+use crate::usage_chart::MeterUsage;
+use crate::Result;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// บิลที่ออกไปแล้ว เก็บข้อมูลทุกอย่างที่ใช้พิมพ์ เพื่อพิมพ์ซ้ำได้เหมือนต้นฉบับ
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IssuedBill {
+    pub bill_number: String,
+    pub issue_date: NaiveDate,
+    pub for_month: String,
+    pub due_days: u32,
+    pub payment_stub: bool,
+    #[serde(default)]
+    pub thai_digits: bool,
+    /// กราฟหน่วยที่ใช้ที่พิมพ์บนบิล (ถ้าพิมพ์กราฟ)
+    #[serde(default)]
+    pub usage_chart: Option<MeterUsage>,
+    /// พิมพ์คำเตือนให้ตรวจสอบรอยรั่ว
+    #[serde(default)]
+    pub leak_notice: bool,
+    pub record: BillRecord,
+}
+
+impl IssuedBill {
+    pub fn new(record: &BillRecord, for_month: &str, options: &BillOptions) -> Self {
+        IssuedBill {
+            bill_number: bill_number(options.issue_date, record.order),
+            issue_date: options.issue_date,
+            for_month: for_month.to_string(),
+            due_days: options.due_days,
+            payment_stub: options.payment_stub,
+            thai_digits: options.thai_digits,
+            usage_chart: options
+                .usage_chart
+                .as_ref()
+                .map(|usage| usage.meter_usage(&record.meter_number)),
+            leak_notice: options.leak_notices.contains(record.meter_number.trim()),
+            record: record.clone(),
+        }
+    }
+}
+
+/// ทะเบียนบิลที่ออกแล้ว เก็บเป็น JSON Lines หนึ่งบิลต่อบรรทัด เรียงตามลำดับที่ออก
+pub struct IssuedStore {
+    bills: Vec<IssuedBill>,
+}
+
+impl IssuedStore {
+    pub fn load(path: &str) -> Result<Self> {
+        if !Path::new(path).exists() {
+            log::log_info(&format!("ยังไม่มีทะเบียนบิล: {}", path));
+            return Ok(IssuedStore { bills: Vec::new() });
+        }
+
+        log::log_info(&format!("กำลังอ่านทะเบียนบิล: {}", path));
+        let bills: Vec<IssuedBill> = fs::read_to_string(path)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?;
+        log::log_debug(&format!("ทะเบียนบิลทั้งหมด {} รายการ", bills.len()));
+        Ok(IssuedStore { bills })
+    }
+
+    /// บันทึกบิลที่เพิ่งออก ถ้าประจำเดือนและเลขที่บิลซ้ำกับที่มีอยู่ (ออกบิลเดือนเดิมซ้ำ) จะเขียนทับ
+    ///
+    /// เลขที่บิลมาจากเดือนที่ออกบิล บิลสองเดือนที่ออกในเดือนเดียวกันจึงได้เลขที่ซ้ำกันได้
+    /// (เช่นออกบิลเดือน ก.ย. ล่าช้าในเดือน ต.ค.) ต้องเทียบประจำเดือนด้วย
+    pub fn record(&mut self, bills: impl IntoIterator<Item = IssuedBill>) {
+        for bill in bills {
+            self.bills.retain(|issued| {
+                issued.for_month != bill.for_month || issued.bill_number != bill.bill_number
+            });
+            self.bills.push(bill);
+        }
+    }
+
+    /// ค้นหาด้วยเลขที่บิล หรือเลขมิเตอร์ (ได้บิลล่าสุดของมิเตอร์นั้น ระบุประจำเดือนเพื่อเลือกเดือนได้)
+    pub fn find(&self, selector: &str, for_month: Option<&str>) -> Option<&IssuedBill> {
+        let selector = selector.trim();
+        self.bills
+            .iter()
+            .rev()
+            .filter(|issued| for_month.is_none_or(|month| issued.for_month == month))
+            .find(|issued| {
+                issued.bill_number == selector || issued.record.meter_number.trim() == selector
+            })
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        if let Some(dir) = Path::new(path).parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut writer = BufWriter::new(File::create(path)?);
+        for bill in &self.bills {
+            serde_json::to_writer(&mut writer, bill)?;
+            writeln!(writer)?;
+        }
+        writer.flush()?;
+        log::log_info(&format!("บันทึกทะเบียนบิล: {}", path));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::HistoryStore;
+    use crate::thai_date::YearMonth;
+    use crate::usage_chart::UsageHistory;
+
+    fn issued(for_month: &str, total_amount: u32) -> IssuedBill {
+        let record = BillRecord {
+            order: 1,
+            meter_number: "M001".to_string(),
+            name: "นายสมชาย".to_string(),
+            previous_reading: Some(1200),
+            current_reading: 1250,
+            units: 50,
+            maintenance_fee: 20,
+            rate_per_unit: 2,
+            total_amount,
+            zone: String::new(),
+        };
+        let options = BillOptions {
+            issue_date: NaiveDate::from_ymd_opt(2025, 10, 5).unwrap(),
+            ..BillOptions::default()
+        };
+        IssuedBill::new(&record, for_month, &options)
+    }
+
+    #[test]
+    fn same_bill_number_in_two_periods_is_kept() {
+        let mut store = IssuedStore { bills: Vec::new() };
+        store.record([issued("ก.ย. 68", 100), issued("ต.ค. 68", 120)]);
+        assert_eq!(store.bills.len(), 2);
+        assert_eq!(store.bills[0].bill_number, store.bills[1].bill_number);
+        assert_eq!(
+            store
+                .find("6810-001", Some("ก.ย. 68"))
+                .unwrap()
+                .record
+                .total_amount,
+            100
+        );
+        assert_eq!(
+            store.find("6810-001", None).unwrap().record.total_amount,
+            120
+        );
+
+        // ออกบิลเดือนเดิมซ้ำ เขียนทับเฉพาะเดือนนั้น
+        store.record([issued("ก.ย. 68", 110)]);
+        assert_eq!(store.bills.len(), 2);
+        assert_eq!(
+            store
+                .find("6810-001", Some("ก.ย. 68"))
+                .unwrap()
+                .record
+                .total_amount,
+            110
+        );
+    }
+
+    #[test]
+    fn usage_chart_is_stored_with_the_bill() {
+        let mut bill = issued("ต.ค. 68", 120);
+        let period: YearMonth = "2025-10".parse().unwrap();
+        let history = HistoryStore::load("tests/ไม่มีไฟล์นี้.csv").unwrap();
+        let chart =
+            UsageHistory::build(&history, period, 6, std::slice::from_ref(&bill.record)).unwrap();
+        bill.usage_chart = Some(chart.meter_usage("M001"));
+
+        let line = serde_json::to_string(&bill).unwrap();
+        let stored: IssuedBill = serde_json::from_str(&line).unwrap();
+        let usage = stored.usage_chart.unwrap();
+        assert_eq!(usage.months.first().unwrap().to_string(), "2025-05");
+        assert_eq!(usage.units.last(), Some(&Some(50)));
+    }
+}
@@ -0,0 +1,109 @@
+use crate::input::{complete_record, ReadReport, RowError};
+use crate::log;
+use crate::model::{BillRecord, ComputedBill};
+use crate::Result;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+fn is_json_lines(file_path: &str) -> bool {
+    matches!(
+        Path::new(file_path)
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .as_deref(),
+        Some("jsonl" | "ndjson")
+    )
+}
+
+/// อ่านข้อมูลบิลจากไฟล์ JSON (array ของรายการ) หรือ JSON Lines (.jsonl/.ndjson หนึ่งรายการต่อบรรทัด)
+/// ชื่อฟิลด์ใช้ได้ทั้งภาษาอังกฤษ (order, meter_number, ...) และชื่อคอลัมน์ภาษาไทยแบบ CSV
+pub fn read_json_file(file_path: &str) -> Result<ReadReport> {
+    log::log_info(&format!("กำลังอ่านไฟล์ JSON: {}", file_path));
+    let text = fs::read_to_string(file_path)?;
+    let text = text.trim_start_matches('\u{feff}');
+
+    // JSON Lines ใช้เลขบรรทัด ส่วน JSON array ใช้ลำดับรายการใน array
+    let report = if is_json_lines(file_path) {
+        records_from_items(
+            text.lines()
+                .enumerate()
+                .filter(|(_, line)| !line.trim().is_empty())
+                .map(|(i, line)| (i as u64 + 1, line.to_string()))
+                .collect(),
+        )
+    } else {
+        let values: Vec<serde_json::Value> = serde_json::from_str(text)?;
+        records_from_json(&values)
+    };
+    log::log_info(&format!(
+        "อ่านข้อมูลสำเร็จ จำนวน {} รายการ",
+        report.records.len()
+    ));
+    Ok(report)
+}
+
+/// อ่านข้อมูลบิลจากรายการ JSON ที่ได้รับมาแล้ว (เช่น จาก API) ตรวจทีละรายการเหมือนอ่านจากไฟล์
+/// เลขบรรทัดของแถวที่ผิดพลาดคือลำดับรายการ (เริ่มที่ 1)
+pub fn records_from_json(values: &[serde_json::Value]) -> ReadReport {
+    records_from_items(
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (i as u64 + 1, value.to_string()))
+            .collect(),
+    )
+}
+
+fn records_from_items(items: Vec<(u64, String)>) -> ReadReport {
+    let mut report = ReadReport {
+        headers: vec!["json".to_string()],
+        ..ReadReport::default()
+    };
+    for (line, item) in items {
+        let parsed = serde_json::from_str::<BillRecord>(&item)
+            .map_err(|error| error.to_string())
+            .and_then(|mut record| complete_record(&mut record).map(|()| record));
+        match parsed {
+            Ok(record) => {
+                log::log_debug(&format!(
+                    "รายการที่ {}: {} - {}",
+                    line, record.meter_number, record.name
+                ));
+                report.records.push(record);
+            }
+            Err(message) => report.errors.push(RowError {
+                line,
+                column: None,
+                value: None,
+                message,
+                raw: vec![item],
+            }),
+        }
+    }
+    report
+}
+
+/// เขียนบิลที่คำนวณแล้วเป็น JSON (.json) หรือ JSON Lines (.jsonl/.ndjson)
+/// ระบุ "-" เพื่อเขียนออก stdout สำหรับส่งต่อให้โปรแกรมอื่น
+pub fn write_json_file(bills: &[ComputedBill], output_path: &str) -> Result<()> {
+    log::log_info(&format!("กำลังบันทึกไฟล์ JSON: {}", output_path));
+    let mut writer: Box<dyn Write> = if output_path == "-" {
+        Box::new(BufWriter::new(io::stdout().lock()))
+    } else {
+        Box::new(BufWriter::new(File::create(output_path)?))
+    };
+
+    if is_json_lines(output_path) || output_path == "-" {
+        for bill in bills {
+            serde_json::to_writer(&mut writer, bill)?;
+            writeln!(writer)?;
+        }
+    } else {
+        serde_json::to_writer_pretty(&mut writer, bills)?;
+        writeln!(writer)?;
+    }
+    writer.flush()?;
+    log::log_info(&format!("บันทึกบิล {} รายการเป็น JSON สำเร็จ", bills.len()));
+    Ok(())
+}
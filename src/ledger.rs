@@ -0,0 +1,144 @@
+use crate::log;
+use crate::model::{BillSummary, ComputedBill};
+use crate::Result;
+use rust_xlsxwriter::{Format, Workbook};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+const HEADERS: [&str; 11] = [
+    "ประจำเดือน",
+    "เลขที่บิล",
+    "ลำดับ",
+    "เลขมิเตอร์",
+    "ชื่อ",
+    "เลขอ่านครั้งก่อน",
+    "เลขอ่านครั้งหลัง",
+    "หน่วย",
+    "ค่าบำรุง",
+    "ค่าน้ำ",
+    "รวม",
+];
+// คอลัมน์แรกที่เป็นตัวเลข (ลำดับ)
+const FIRST_NUMBER_COLUMN: usize = 2;
+
+// ค่าแต่ละช่องในหนึ่งแถว: ข้อความ หรือตัวเลข (None = ช่องว่าง)
+// ตัวเลขเป็น u64 เพราะแถวยอดรวมเกินขอบเขตของ u32 ได้
+enum Cell {
+    Text(String),
+    Number(Option<u64>),
+}
+
+fn bill_row(bill: &ComputedBill) -> Vec<Cell> {
+    let record = bill.record;
+    vec![
+        Cell::Text(bill.for_month.to_string()),
+        Cell::Text(bill.bill_number.clone()),
+        Cell::Number(Some(record.order.into())),
+        Cell::Text(record.meter_number.clone()),
+        Cell::Text(record.name.clone()),
+        Cell::Number(record.previous_reading.map(u64::from)),
+        Cell::Number(Some(record.current_reading.into())),
+        Cell::Number(Some(record.units.into())),
+        Cell::Number(Some(record.maintenance_fee.into())),
+        Cell::Number(Some(bill.units_cost.into())),
+        Cell::Number(Some(record.total_amount.into())),
+    ]
+}
+
+fn totals_row(summary: &BillSummary) -> Vec<Cell> {
+    vec![
+        Cell::Text(String::new()),
+        Cell::Text(format!("{} บิล", summary.bill_count)),
+        Cell::Number(None),
+        Cell::Text(String::new()),
+        Cell::Text("รวมทั้งสิ้น".to_string()),
+        Cell::Number(None),
+        Cell::Number(None),
+        Cell::Number(Some(summary.total_units)),
+        Cell::Number(Some(summary.total_maintenance_fee)),
+        Cell::Number(Some(summary.total_units_cost)),
+        Cell::Number(Some(summary.total_amount)),
+    ]
+}
+
+/// บันทึกบัญชีรายรับของเดือนเป็น .xlsx หรือ CSV (ตามนามสกุลไฟล์) พร้อมแถวรวมท้ายตาราง
+pub fn write_ledger(bills: &[ComputedBill], output_path: &str) -> Result<()> {
+    log::log_info(&format!("กำลังบันทึกบัญชีรายรับ: {}", output_path));
+    let summary = BillSummary::from_records(bills.iter().map(|bill| bill.record));
+
+    let mut rows: Vec<Vec<Cell>> = bills.iter().map(bill_row).collect();
+    rows.push(totals_row(&summary));
+
+    let is_xlsx = Path::new(output_path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("xlsx"));
+    if is_xlsx {
+        write_xlsx(&rows, output_path)?;
+    } else {
+        write_csv(&rows, output_path)?;
+    }
+    log::log_info(&format!(
+        "บันทึกบัญชีรายรับ {} รายการ รวม {} บาท",
+        summary.bill_count, summary.total_amount
+    ));
+    Ok(())
+}
+
+fn write_csv(rows: &[Vec<Cell>], output_path: &str) -> Result<()> {
+    let mut file = BufWriter::new(File::create(output_path)?);
+    // ใส่ BOM ให้ Excel เปิดภาษาไทยได้ถูกต้อง
+    file.write_all("\u{feff}".as_bytes())?;
+    let mut writer = csv::Writer::from_writer(file);
+    writer.write_record(HEADERS)?;
+    for row in rows {
+        writer.write_record(row.iter().map(|cell| match cell {
+            Cell::Text(text) => text.clone(),
+            Cell::Number(Some(value)) => value.to_string(),
+            Cell::Number(None) => String::new(),
+        }))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_xlsx(rows: &[Vec<Cell>], output_path: &str) -> Result<()> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("บัญชีรายรับ")?;
+    let bold = Format::new().set_bold();
+    let number = Format::new().set_num_format("#,##0");
+    let bold_number = Format::new().set_bold().set_num_format("#,##0");
+
+    for (col, header) in HEADERS.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, *header, &bold)?;
+        let width = if col < FIRST_NUMBER_COLUMN { 12 } else { 14 };
+        worksheet.set_column_width(col as u16, width)?;
+    }
+    worksheet.set_column_width(4, 28)?;
+
+    let last_row = rows.len();
+    for (i, row) in rows.iter().enumerate() {
+        let row_index = (i + 1) as u32;
+        let is_totals = i + 1 == last_row;
+        for (col, cell) in row.iter().enumerate() {
+            let col = col as u16;
+            match cell {
+                Cell::Text(text) if is_totals => {
+                    worksheet.write_string_with_format(row_index, col, text, &bold)?;
+                }
+                Cell::Text(text) => {
+                    worksheet.write_string(row_index, col, text)?;
+                }
+                Cell::Number(Some(value)) => {
+                    let format = if is_totals { &bold_number } else { &number };
+                    worksheet.write_number_with_format(row_index, col, *value as f64, format)?;
+                }
+                Cell::Number(None) => {}
+            }
+        }
+    }
+
+    workbook.save(output_path)?;
+    Ok(())
+}
@@ -0,0 +1,88 @@
+//! ไลบรารีออกใบเสร็จค่าน้ำประปาหมู่บ้าน หมู่ 3
+//!
+//! โปรแกรม `papa_moo_3_bills` เป็นเพียงตัวรับคำสั่งจากบรรทัดคำสั่ง งานทั้งหมดอยู่ในไลบรารีนี้
+//! เครื่องมืออื่นจึงเรียกใช้ได้โดยตรง:
+//!
+//! - ข้อมูลบิลและการคำนวณค่าน้ำ: [`model`]
+//! - อ่านข้อมูล CSV/Excel/ODS/JSON: [`input::read_records`] ([`csv_util`], [`sheet_util`], [`json_util`])
+//! - สร้าง PDF ใบเสร็จ รายงานสรุป และใบจดเลขมิเตอร์: [`pdf_util::create_pdf`], [`summary_pdf`], [`route_sheet`]
+//! - ฟอนต์ภาษาไทย: [`font_util::find_thai_font`]
+//! - ข้อผิดพลาดแยกประเภท พร้อมรหัสจบการทำงาน: [`error::Error`]
+//! - หน้าเว็บสำหรับผู้ที่ไม่ถนัดบรรทัดคำสั่ง (feature `web`): `web::serve`
+//! - กรอกเลขอ่านมิเตอร์ในหน้าจอเทอร์มินัล (feature `tui`): `tui::enter_readings`
+//! - ส่งไฟล์ไปพิมพ์ที่เครื่องพิมพ์ CUPS/IPP: [`printer::print_file`]
+//! - ส่งใบเสร็จทางอีเมล (feature `email`): `email::email_bills`
+//! - แจ้งบิลทาง LINE หรือ webhook พร้อม QR พร้อมเพย์ (feature `notify`): `notify::notify_bills`
+//! - ประวัติการอ่านมิเตอร์ ทะเบียนบิล และการตรวจเลขอ่านผิดปกติ: [`history`], [`issued`], [`anomaly`]
+//! - ขั้นตอนออกบิลประจำเดือนที่ใช้ทั้งบรรทัดคำสั่งและหน้าเว็บ: [`billing::prepare_bills`]
+//!
+//! ```no_run
+//! use papa_moo_3_bills::input::{read_records, InputOptions};
+//! use papa_moo_3_bills::pdf_util::{create_pdf, BillOptions};
+//!
+//! let records = read_records("input-csv/bills_oct_68.csv", &InputOptions::default())?
+//!     .into_records(false)?;
+//! create_pdf(&records, "bills/plumbing_oct_68.pdf", "ต.ค. 68", &BillOptions::default())?;
+//! # Ok::<(), papa_moo_3_bills::error::Error>(())
+//! ```
+
+/// ตรวจเลขอ่านมิเตอร์ที่ผิดปกติ (ใช้น้ำสูงผิดปกติ ไม่มีการใช้น้ำ เลขอ่านลดลง)
+pub mod anomaly;
+/// ขั้นตอนออกบิลประจำเดือน (ยกเลขอ่าน ตรวจเลขอ่านผิดปกติ บันทึกประวัติและทะเบียนบิล)
+pub mod billing;
+/// อ่านไฟล์ CSV (รองรับหลาย encoding และตัวคั่น)
+pub mod csv_util;
+/// บันทึกสถานะการส่งบิล (อีเมล LINE webhook) เพื่อไม่ส่งซ้ำเมื่อรันใหม่
+pub mod delivery;
+/// เส้นและรูปทรงสำหรับวาดบน PDF
+pub mod drawing;
+/// ส่งใบเสร็จและรายงานสรุปทางอีเมลผ่าน SMTP
+#[cfg(feature = "email")]
+pub mod email;
+/// ข้อผิดพลาดของโปรแกรมและรหัสจบการทำงาน
+pub mod error;
+/// ค้นหาและดาวน์โหลดฟอนต์ภาษาไทย
+pub mod font_util;
+/// ประวัติการอ่านมิเตอร์รายเดือน และการยกเลขอ่านจากเดือนก่อน
+pub mod history;
+/// อ่านข้อมูลบิลจากไฟล์ทุกรูปแบบ พร้อมรายงานแถวที่ผิดพลาด
+pub mod input;
+/// ทะเบียนบิลที่ออกแล้ว สำหรับพิมพ์ซ้ำ
+pub mod issued;
+/// อ่านและเขียนไฟล์ JSON / JSON Lines
+pub mod json_util;
+/// บันทึกบัญชีรายรับเป็น CSV/XLSX
+pub mod ledger;
+/// ข้อความ log ของโปรแกรม
+pub mod log;
+/// ข้อมูลบิล การคำนวณค่าน้ำ และยอดรวม
+pub mod model;
+/// แจ้งบิลทาง LINE หรือ webhook
+#[cfg(feature = "notify")]
+pub mod notify;
+/// สร้าง PDF ใบเสร็จ
+pub mod pdf_util;
+/// ส่งไฟล์ PDF ไปพิมพ์ที่เครื่องพิมพ์ CUPS หรือ IPP โดยตรง
+pub mod printer;
+/// ข้อความและภาพ QR พร้อมเพย์ตามยอดเงินของบิล
+#[cfg(feature = "notify")]
+pub mod promptpay;
+/// สร้าง PDF ใบจดเลขมิเตอร์
+pub mod route_sheet;
+/// อ่านไฟล์ Excel/ODS
+pub mod sheet_util;
+/// สร้าง PDF สรุปการเก็บเงินประจำเดือน
+pub mod summary_pdf;
+/// วันที่และเดือนแบบไทย (พ.ศ.)
+pub mod thai_date;
+/// หน้าจอเทอร์มินัลสำหรับกรอกเลขอ่านมิเตอร์ประจำเดือน (feature `tui`)
+#[cfg(feature = "tui")]
+pub mod tui;
+/// กราฟหน่วยที่ใช้ย้อนหลังบนบิล
+pub mod usage_chart;
+/// หน้าเว็บสำหรับอัปโหลดไฟล์ข้อมูลและดาวน์โหลดใบเสร็จ (feature `web`)
+#[cfg(feature = "web")]
+pub mod web;
+
+/// ผลลัพธ์ของฟังก์ชันในไลบรารี
+pub type Result<T, E = error::Error> = std::result::Result<T, E>;
@@ -55,6 +55,7 @@ pub fn log_debug(message: &str) {
     debug!("{}", message);
 }
 
+#[allow(dead_code)]
 pub fn log_trace(message: &str) {
     trace!("{}", message);
 }
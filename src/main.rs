@@ -1,32 +1,493 @@
-use csv_util::read_csv_file;
-use pdf_util::create_pdf;
-use std::error::Error;
-
-mod csv_util;
-mod drawing;
-mod font_util;
-mod log;
-mod model;
-mod pdf_util;
-
-fn main() -> Result<(), Box<dyn Error>> {
+use clap::Parser;
+use cli::{Cli, Command};
+use papa_moo_3_bills::billing::{prepare_bills, BillingSettings};
+#[cfg(any(feature = "email", feature = "notify"))]
+use papa_moo_3_bills::csv_util::read_contacts;
+#[cfg(any(feature = "email", feature = "notify"))]
+use papa_moo_3_bills::delivery::{DeliveryLog, DeliveryReport};
+#[cfg(feature = "email")]
+use papa_moo_3_bills::email::{
+    self, email_bills, email_summary, EmailTemplate, Mailer, SmtpSettings,
+};
+use papa_moo_3_bills::error::Error;
+#[cfg(feature = "tui")]
+use papa_moo_3_bills::history::HistoryStore;
+use papa_moo_3_bills::input::{read_records, HeaderAliases, InputOptions};
+use papa_moo_3_bills::issued::IssuedStore;
+use papa_moo_3_bills::json_util::write_json_file;
+use papa_moo_3_bills::ledger::write_ledger;
+use papa_moo_3_bills::log;
+use papa_moo_3_bills::model::{BillRecord, ComputedBill};
+#[cfg(feature = "notify")]
+use papa_moo_3_bills::notify::{
+    self, notify_bills, JsonWebhookNotifier, LineNotifier, Notifier, NotifyChannel, NotifySettings,
+    LINE_PUSH_URL,
+};
+use papa_moo_3_bills::pdf_util::{create_pdf, create_per_customer_pdfs, BillOptions};
+use papa_moo_3_bills::printer::{print_file, PrintOptions, PrintTarget};
+use papa_moo_3_bills::route_sheet::create_route_sheet_pdf;
+use papa_moo_3_bills::summary_pdf::create_summary_pdf;
+use papa_moo_3_bills::thai_date::{format_date, MonthName, YearMonth};
+use papa_moo_3_bills::usage_chart::UsageHistory;
+use papa_moo_3_bills::Result;
+#[cfg(feature = "notify")]
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::Path;
+use std::process::ExitCode;
+
+mod cli;
+
+fn main() -> ExitCode {
     // Initialize logger
     log::init_logger();
 
-    //----- INPUT DATA
-    let input_path = "./input-csv/bills_oct_68.csv";
-    let for_month = "ต.ค.";
-    let bill_name = "bills/plumbing_oct_68.pdf";
-    //----- INPUT DATA
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            // รหัสจบการทำงานแยกตามประเภทข้อผิดพลาด ให้สคริปต์ตรวจสอบได้
+            log::log_error(&error.to_string());
+            ExitCode::from(error.exit_code())
+        }
+    }
+}
+
+fn run(cli: &Cli) -> Result<()> {
+    if let Some(Command::RouteSheet {
+        input,
+        month,
+        output,
+        sheet,
+    }) = &cli.command
+    {
+        log::log_info("=== เริ่มสร้างใบจดเลขมิเตอร์ ===");
+        let options = InputOptions {
+            sheet: sheet.clone(),
+            aliases: load_header_aliases(cli)?,
+        };
+        let records = load_records(input, &options, cli)?;
+        create_route_sheet_pdf(
+            &records,
+            output,
+            &month.long_label(),
+            cli.issue_date
+                .unwrap_or_else(|| chrono::Local::now().date_naive()),
+        )?;
+        log::log_info("=== จบการทำงาน ===");
+        return Ok(());
+    }
+
+    #[cfg(feature = "tui")]
+    if let Some(Command::Enter {
+        input,
+        sheet,
+        month,
+        output,
+        bills,
+    }) = &cli.command
+    {
+        use papa_moo_3_bills::csv_util::write_csv_file;
+        use papa_moo_3_bills::tui::{enter_readings, EntryOutcome};
 
+        log::log_info("=== เริ่มกรอกเลขอ่านมิเตอร์ ===");
+        let options = InputOptions {
+            sheet: sheet.clone(),
+            aliases: load_header_aliases(cli)?,
+        };
+        let previous = load_records(input, &options, cli)?;
+        let history = if cli.no_history {
+            None
+        } else {
+            Some(HistoryStore::load(&cli.history)?)
+        };
+        match enter_readings(&previous, *month, history.as_ref(), cli.anomaly_factor)? {
+            EntryOutcome::Saved(records) => {
+                write_csv_file(&records, output)?;
+                if *bills {
+                    return issue_bills(cli, output, Some(*month));
+                }
+            }
+            EntryOutcome::Cancelled => log::log_info("ยกเลิกการกรอกเลขอ่าน ไม่ได้บันทึกไฟล์"),
+        }
+        log::log_info("=== จบการทำงาน ===");
+        return Ok(());
+    }
+
+    #[cfg(feature = "web")]
+    if let Some(Command::Serve { address, profiles }) = &cli.command {
+        log::log_info("=== เปิดหน้าเว็บออกใบเสร็จ ===");
+        let profiles = match profiles {
+            Some(path) => papa_moo_3_bills::web::load_profiles(path)?,
+            None => Vec::new(),
+        };
+        return papa_moo_3_bills::web::serve(
+            address,
+            load_header_aliases(cli)?,
+            billing_settings(cli),
+            profiles,
+        );
+    }
+
+    if let Some(Command::Reprint {
+        bill,
+        month,
+        output,
+        copies,
+    }) = &cli.command
+    {
+        log::log_info("=== เริ่มพิมพ์บิลซ้ำ ===");
+        let store = IssuedStore::load(&cli.issued)?;
+        let issued = store
+            .find(bill, month.map(|month| month.label()).as_deref())
+            .ok_or_else(|| {
+                Error::Validation(format!("ไม่พบบิล \"{}\" ในทะเบียนบิล {}", bill, cli.issued))
+            })?;
+        log::log_info(&format!(
+            "พิมพ์ซ้ำบิลเลขที่ {} ประจำเดือน {}: {} - {}",
+            issued.bill_number, issued.for_month, issued.record.meter_number, issued.record.name
+        ));
+        // กราฟหน่วยที่ใช้มาจากทะเบียนบิล ไม่อ่านไฟล์ประวัติใหม่ (ประวัติอาจแก้ไปแล้ว)
+        let usage_chart = issued
+            .usage_chart
+            .clone()
+            .map(|usage| UsageHistory::from_meter_usage(&issued.record.meter_number, usage))
+            .transpose()?;
+        let options = BillOptions {
+            copy_mode: *copies,
+            payment_stub: issued.payment_stub,
+            due_days: issued.due_days,
+            issue_date: issued.issue_date,
+            reprint: true,
+            thai_digits: issued.thai_digits,
+            usage_chart,
+            leak_notices: if issued.leak_notice {
+                HashSet::from([issued.record.meter_number.trim().to_string()])
+            } else {
+                HashSet::new()
+            },
+        };
+        let output = output
+            .clone()
+            .unwrap_or_else(|| format!("bills/reprint_{}.pdf", issued.bill_number));
+        let paths = create_pdf(
+            std::slice::from_ref(&issued.record),
+            &output,
+            &issued.for_month,
+            &options,
+        )?;
+        print_outputs(cli, &paths)?;
+        log::log_info("=== จบการทำงาน ===");
+        return Ok(());
+    }
+
+    issue_bills(cli, &cli.input, cli.month)
+}
+
+// ออกบิลประจำเดือนจากไฟล์ข้อมูล `input` ตามตัวเลือกของคำสั่งหลัก
+fn issue_bills(cli: &Cli, input: &str, month: Option<YearMonth>) -> Result<()> {
     log::log_info("=== เริ่มโปรแกรมสร้างใบเสร็จ ===");
 
-    let records = read_csv_file(input_path)?;
-    log::log_info(&format!("อ่านข้อมูลจาก CSV สำเร็จ: {} รายการ", records.len()));
+    // ไม่ระบุวันออกบิลใช้วันนี้ PDF ที่สร้างต่างวันจึงต่างกัน (ระบุ --issue-date เพื่อให้เหมือนเดิมทุกไบต์)
+    // ประจำเดือนมาจากเดือนของวันออกบิล ถ้าไม่ได้ระบุ
+    let issue_date = cli
+        .issue_date
+        .unwrap_or_else(|| chrono::Local::now().date_naive());
+    let period = month.unwrap_or_else(|| YearMonth::from_date(issue_date));
+    let for_month = period.label();
+    log::log_info(&format!(
+        "ประจำเดือน {} วันออกบิล {}",
+        period.long_label(),
+        format_date(issue_date, MonthName::Long, false)
+    ));
+
+    let input_options = InputOptions {
+        sheet: cli.sheet.clone(),
+        aliases: load_header_aliases(cli)?,
+    };
+    let records = load_records(input, &input_options, cli)?;
+    log::log_info(&format!("อ่านข้อมูลสำเร็จ: {} รายการ", records.len()));
+
+    // เลขอ่านครั้งหลังของเดือนก่อนจากไฟล์ที่ระบุ ใช้แทนไฟล์ประวัติ
+    let previous = match &cli.previous {
+        Some(previous_path) => {
+            let previous_options = InputOptions {
+                sheet: cli.previous_sheet.clone(),
+                ..input_options.clone()
+            };
+            Some(load_records(previous_path, &previous_options, cli)?)
+        }
+        None => None,
+    };
+    let billing = billing_settings(cli);
+    let mut options = BillOptions {
+        copy_mode: cli.copies,
+        payment_stub: cli.payment_stub,
+        due_days: cli.due_days,
+        issue_date,
+        reprint: false,
+        thai_digits: cli.thai_digits,
+        ..BillOptions::default()
+    };
+    let mut bills = prepare_bills(records, period, previous.as_deref(), &billing, &mut options)?;
+    let records = &bills.records;
+    let selected = select_records(records, &cli.only)?;
+    let bill_paths = if let Some(pattern) = &cli.per_customer {
+        let paths = create_per_customer_pdfs(&selected, pattern, &for_month, &options)?;
+        log::log_info(&format!("สร้างไฟล์ PDF แยกรายผู้ใช้น้ำสำเร็จ {} ไฟล์", paths.len()));
+        paths
+    } else {
+        let paths = create_pdf(&selected, &cli.output, &for_month, &options)?;
+        log::log_info("สร้างไฟล์ PDF สำเร็จ");
+        paths
+    };
+
+    if let Some(summary_path) = &cli.summary {
+        create_summary_pdf(records, summary_path, &period.long_label(), issue_date)?;
+        log::log_info("สร้างไฟล์ PDF สรุปประจำเดือนสำเร็จ");
+    }
 
-    create_pdf(&records, bill_name, for_month)?;
-    log::log_info("สร้างไฟล์ PDF สำเร็จ");
+    if cli.json_output.is_some() || cli.ledger.is_some() {
+        let computed = ComputedBill::from_records(records, &for_month, issue_date);
+        if let Some(json_path) = &cli.json_output {
+            write_json_file(&computed, json_path)?;
+        }
+        if let Some(ledger_path) = &cli.ledger {
+            write_ledger(&computed, ledger_path)?;
+            log::log_info("บันทึกบัญชีรายรับสำเร็จ");
+        }
+    }
+
+    bills.save(Some(&selected), &for_month, &options, &billing)?;
+
+    // ส่งอีเมลหรือแจ้งบิลไม่สำเร็จก็ยังส่งทางช่องทางอื่นและพิมพ์ต่อ แล้วจึงรายงานข้อผิดพลาด
+    #[cfg(feature = "email")]
+    let email_result = send_emails(cli, &selected, &for_month, period, &options);
+    #[cfg(feature = "notify")]
+    let notify_result = send_notifications(cli, &selected, &for_month, &options);
+    let print_result = print_outputs(cli, &bill_paths);
+    #[cfg(feature = "email")]
+    email_result?;
+    #[cfg(feature = "notify")]
+    notify_result?;
+    print_result?;
 
     log::log_info("=== จบการทำงาน ===");
     Ok(())
 }
+
+// ส่งใบเสร็จถึงอีเมลของผู้ใช้น้ำ และส่งสรุปประจำเดือน ส่งหลังบันทึกประวัติแล้ว
+// ถ้าบางฉบับส่งไม่สำเร็จ บิลยังออกครบ รันซ้ำได้โดยจะส่งเฉพาะฉบับที่ยังไม่สำเร็จ
+#[cfg(feature = "email")]
+fn send_emails(
+    cli: &Cli,
+    records: &[BillRecord],
+    for_month: &str,
+    period: YearMonth,
+    options: &BillOptions,
+) -> Result<()> {
+    let args = &cli.email;
+    if args.email_contacts.is_none() && args.email_summary_to.is_empty() {
+        return Ok(());
+    }
+    let (Some(host), Some(from)) = (&args.smtp_host, &args.smtp_from) else {
+        return Err(Error::Validation(
+            "ต้องระบุ --smtp-host และ --smtp-from เพื่อส่งอีเมล".to_string(),
+        ));
+    };
+    let mailer = Mailer::new(&SmtpSettings {
+        host: host.clone(),
+        port: args.smtp_port,
+        security: args.smtp_security,
+        username: args.smtp_user.clone(),
+        password: args.smtp_password.clone(),
+        from: from.clone(),
+    })?;
+
+    let mut report = DeliveryReport::default();
+    let mut delivery_log = DeliveryLog::load(&args.email_status)?;
+    if let Some(contacts_path) = &args.email_contacts {
+        let contacts = read_contacts(contacts_path, &email::CONTACT_COLUMNS)?;
+        let template = match &args.email_template {
+            Some(template_path) => EmailTemplate::load(template_path)?,
+            None => EmailTemplate::default(),
+        };
+        report = email_bills(
+            &mailer,
+            records,
+            for_month,
+            options,
+            &contacts,
+            &template,
+            &mut delivery_log,
+        )?;
+    }
+    if let Some(summary_path) = &cli.summary {
+        report.merge(email_summary(
+            &mailer,
+            &args.email_summary_to,
+            summary_path,
+            &period.long_label(),
+            &mut delivery_log,
+        )?);
+    }
+    report.into_result("ส่งอีเมล", &args.email_status)
+}
+
+// แจ้งบิลของแต่ละรายทาง LINE หรือ webhook
+#[cfg(feature = "notify")]
+fn send_notifications(
+    cli: &Cli,
+    records: &[BillRecord],
+    for_month: &str,
+    options: &BillOptions,
+) -> Result<()> {
+    let args = &cli.notify;
+    let Some(channel) = args.notify else {
+        return Ok(());
+    };
+    let notifier: Box<dyn Notifier> = match channel {
+        NotifyChannel::Line => {
+            if args.promptpay.is_some() && args.qr_base_url.is_none() {
+                log::log_warn(
+                    "LINE แสดงภาพ QR จากลิงก์เท่านั้น ระบุ --qr-base-url เพื่อแนบ QR (ครั้งนี้ส่งเฉพาะข้อความ)",
+                );
+            }
+            Box::new(LineNotifier {
+                url: args
+                    .notify_url
+                    .clone()
+                    .unwrap_or_else(|| LINE_PUSH_URL.to_string()),
+                token: args.notify_token.clone().unwrap_or_default(),
+            })
+        }
+        NotifyChannel::Webhook => Box::new(JsonWebhookNotifier {
+            url: args.notify_url.clone().unwrap_or_default(),
+            token: args.notify_token.clone(),
+        }),
+    };
+    let recipients = match &args.notify_contacts {
+        Some(contacts_path) => read_contacts(contacts_path, &notify::CONTACT_COLUMNS)?,
+        None => HashMap::new(),
+    };
+    let settings = NotifySettings {
+        promptpay: args.promptpay.clone(),
+        qr_dir: args.qr_dir.clone(),
+        qr_base_url: args.qr_base_url.clone(),
+        retries: args.notify_retries,
+        dry_run: args.notify_dry_run,
+    };
+    let mut delivery_log = DeliveryLog::load(&args.notify_status)?;
+    notify_bills(
+        notifier.as_ref(),
+        records,
+        for_month,
+        options,
+        &recipients,
+        &settings,
+        &mut delivery_log,
+    )?
+    .into_result("แจ้งบิล", &args.notify_status)
+}
+
+// ตัวเลือกขั้นตอนออกบิลจากบรรทัดคำสั่ง (ใช้ทั้งคำสั่งหลักและหน้าเว็บ)
+fn billing_settings(cli: &Cli) -> BillingSettings {
+    BillingSettings {
+        history: (!cli.no_history).then(|| cli.history.clone()),
+        issued: (!cli.no_history).then(|| cli.issued.clone()),
+        anomaly_factor: cli.anomaly_factor,
+        leak_notice: cli.leak_notice,
+        usage_chart: cli.usage_chart,
+    }
+}
+
+// ส่งไฟล์บิลไปพิมพ์ที่เครื่องพิมพ์ที่ระบุด้วย --printer
+// ไม่มีบันทึกการพิมพ์ รันคำสั่งเดิมซ้ำจะพิมพ์ทุกไฟล์อีกครั้ง
+fn print_outputs(cli: &Cli, paths: &[String]) -> Result<()> {
+    let Some(printer) = &cli.printer else {
+        return Ok(());
+    };
+    let target = PrintTarget::parse(printer);
+    let options = PrintOptions {
+        copies: cli.print_copies,
+        duplex: cli.duplex,
+        tray: cli.tray.clone(),
+    };
+    for path in paths {
+        print_file(path, &target, &options)?;
+    }
+    log::log_info(&format!(
+        "ส่งงานพิมพ์ไปยัง {} สำเร็จ {} ไฟล์",
+        printer,
+        paths.len()
+    ));
+    Ok(())
+}
+
+// เลือกเฉพาะบิลที่ระบุด้วย --only (ลำดับหรือเลขมิเตอร์) ถ้าไม่ระบุใช้ทุกบิล
+fn select_records(records: &[BillRecord], selectors: &[String]) -> Result<Vec<BillRecord>> {
+    if selectors.is_empty() {
+        return Ok(records.to_vec());
+    }
+    let matches = |record: &BillRecord, selector: &str| {
+        record.meter_number.trim() == selector
+            || selector
+                .parse::<u32>()
+                .is_ok_and(|order| order == record.order)
+    };
+    for selector in selectors.iter().map(|s| s.trim()) {
+        if !records.iter().any(|record| matches(record, selector)) {
+            log::log_warn(&format!("ไม่พบผู้ใช้น้ำลำดับหรือเลขมิเตอร์ \"{}\"", selector));
+        }
+    }
+    let selected: Vec<BillRecord> = records
+        .iter()
+        .filter(|record| {
+            selectors
+                .iter()
+                .any(|selector| matches(record, selector.trim()))
+        })
+        .cloned()
+        .collect();
+    if selected.is_empty() {
+        return Err(Error::Validation("ไม่พบผู้ใช้น้ำตามที่ระบุใน --only".to_string()));
+    }
+    log::log_info(&format!("เลือกพิมพ์เฉพาะ {} รายการ", selected.len()));
+    Ok(selected)
+}
+
+// ชื่อคอลัมน์มาตรฐาน และชื่อเพิ่มเติมจากไฟล์ที่ผู้ใช้ระบุ
+fn load_header_aliases(cli: &Cli) -> Result<HeaderAliases> {
+    let mut aliases = HeaderAliases::default();
+    if let Some(alias_path) = &cli.header_aliases {
+        aliases.load_file(alias_path)?;
+    }
+    Ok(aliases)
+}
+
+// อ่านไฟล์ข้อมูลและรายงานแถวที่ผิดพลาดทั้งหมด ถ้าเลือก --skip-invalid จะข้ามแถวเหล่านั้น
+// และบันทึกไว้ในไฟล์แยกเพื่อแก้ไข
+fn load_records(file_path: &str, options: &InputOptions, cli: &Cli) -> Result<Vec<BillRecord>> {
+    let report = read_records(file_path, options)?;
+    report.log_errors(file_path);
+    if cli.skip_invalid && !report.errors.is_empty() {
+        let rejected_path = cli
+            .rejected
+            .clone()
+            .unwrap_or_else(|| rejected_path_for(file_path));
+        report.write_rejected(&rejected_path)?;
+    }
+    report.into_records(cli.skip_invalid)
+}
+
+// input-csv/bills_oct_68.csv -> input-csv/bills_oct_68_rejected.csv
+fn rejected_path_for(file_path: &str) -> String {
+    let path = Path::new(file_path);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}_rejected.csv", stem))
+        .to_string_lossy()
+        .into_owned()
+}
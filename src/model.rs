@@ -1,23 +1,401 @@
-use serde::Deserialize;
+use crate::thai_date::{buddhist_year, from_thai_digits};
+use chrono::{Datelike, NaiveDate};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
 
-#[derive(Debug, Deserialize)]
+// ชื่อคอลัมน์ภาษาไทยใช้กับไฟล์ CSV/Excel (ดู CSV_COLUMNS) ส่วน JSON ใช้ชื่อภาษาอังกฤษ (อ่านได้ทั้งสองแบบ)
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct BillRecord {
-    #[serde(rename = "ลำดับ")]
+    #[serde(
+        rename(deserialize = "ลำดับ", serialize = "order"),
+        alias = "order",
+        deserialize_with = "thai_number"
+    )]
     pub order: u32,
-    #[serde(rename = "เลขมิเตอร์")]
+    #[serde(
+        rename(deserialize = "เลขมิเตอร์", serialize = "meter_number"),
+        alias = "meter_number"
+    )]
     pub meter_number: String,
-    #[serde(rename = "ชื่อ")]
+    #[serde(rename(deserialize = "ชื่อ", serialize = "name"), alias = "name")]
     pub name: String,
-    #[serde(rename = "เลขอ่านครั้งก่อน")]
-    pub previous_reading: u32,
-    #[serde(rename = "เลขอ่านครั้งหลัง")]
+    // เว้นว่างได้ (None) ระบบจะเติมจากเลขอ่านครั้งหลังของเดือนก่อน ต่างจากเลขอ่าน 0 ที่กรอกมา
+    #[serde(
+        rename(deserialize = "เลขอ่านครั้งก่อน", serialize = "previous_reading"),
+        alias = "previous_reading",
+        default,
+        deserialize_with = "optional_number"
+    )]
+    pub previous_reading: Option<u32>,
+    #[serde(
+        rename(deserialize = "เลขอ่านครั้งหลัง", serialize = "current_reading"),
+        alias = "current_reading",
+        deserialize_with = "thai_number"
+    )]
     pub current_reading: u32,
-    #[serde(rename = "หน่วย")]
+    #[serde(
+        rename(deserialize = "หน่วย", serialize = "units"),
+        alias = "units",
+        default,
+        deserialize_with = "empty_as_zero"
+    )]
     pub units: u32,
-    #[serde(rename = "20")]
+    #[serde(
+        rename(deserialize = "20", serialize = "maintenance_fee"),
+        alias = "maintenance_fee",
+        deserialize_with = "thai_number"
+    )]
     pub maintenance_fee: u32,
-    #[serde(rename = "หนวยละ")]
+    #[serde(
+        rename(deserialize = "หนวยละ", serialize = "rate_per_unit"),
+        alias = "rate_per_unit",
+        deserialize_with = "thai_number"
+    )]
     pub rate_per_unit: u32,
-    #[serde(rename = "จำนวน")]
+    #[serde(
+        rename(deserialize = "จำนวน", serialize = "total_amount"),
+        alias = "total_amount",
+        default,
+        deserialize_with = "empty_as_zero"
+    )]
     pub total_amount: u32,
+    // คอลัมน์ไม่บังคับ ใช้จัดกลุ่มใบจดเลขมิเตอร์ตามเส้นทาง
+    #[serde(
+        rename(deserialize = "โซน", serialize = "zone"),
+        alias = "zone",
+        default
+    )]
+    pub zone: String,
+}
+
+impl BillRecord {
+    /// ค่าน้ำตามหน่วยที่ใช้ (หน่วย * หน่วยละ) ถ้าเกินขอบเขตตัวเลขจะได้ค่าสูงสุด
+    /// (แถวแบบนี้ถูกปฏิเสธตอนอ่านไฟล์แล้ว ดู `checked_total`)
+    pub fn units_cost(&self) -> u32 {
+        self.units.saturating_mul(self.rate_per_unit)
+    }
+
+    /// ยอดเงิน (ค่าบำรุง + หน่วย * หน่วยละ) หรือ `None` ถ้าเกินขอบเขตตัวเลข
+    pub fn checked_total(&self) -> Option<u32> {
+        self.units
+            .checked_mul(self.rate_per_unit)?
+            .checked_add(self.maintenance_fee)
+    }
+
+    /// เลขอ่านครั้งก่อน หรือ 0 ถ้ายังไม่ได้กรอก (บิลที่ผ่าน `complete` แล้วมีค่าเสมอ)
+    pub fn previous(&self) -> u32 {
+        self.previous_reading.unwrap_or(0)
+    }
+
+    /// คำนวณจำนวนหน่วยและยอดเงินใหม่จากเลขอ่านมิเตอร์
+    pub fn recalculate(&mut self) {
+        self.units = self.current_reading.saturating_sub(self.previous());
+        self.total_amount = self.checked_total().unwrap_or(u32::MAX);
+    }
+
+    /// เติมหน่วยและยอดเงินที่เว้นว่างไว้ หลังยกเลขอ่านครั้งก่อนจากเดือนก่อนแล้ว
+    /// หน่วยและยอดเงินที่กรอกมาใช้ตามเดิม คืนข้อความผิดพลาดถ้ายังไม่มีเลขอ่านครั้งก่อน
+    /// หรือยอดเงินเกินขอบเขตตัวเลข
+    pub fn complete(&mut self) -> Result<(), String> {
+        let Some(previous) = self.previous_reading else {
+            return Err("ไม่ได้กรอกเลขอ่านครั้งก่อน และไม่มีเลขอ่านครั้งหลังของเดือนก่อน".to_string());
+        };
+        if self.units == 0 {
+            self.units = self.current_reading.saturating_sub(previous);
+        }
+        let total = self.checked_total().ok_or_else(|| {
+            format!(
+                "ยอดเงินเกินขอบเขตตัวเลข ({} หน่วย หน่วยละ {} บาท)",
+                self.units, self.rate_per_unit
+            )
+        })?;
+        if self.total_amount == 0 {
+            self.total_amount = total;
+        }
+        Ok(())
+    }
+}
+
+/// เลขที่บิล: ปี พ.ศ. 2 หลัก + เดือนที่ออกบิล + ลำดับ เช่น 6810-001
+pub fn bill_number(issue_date: NaiveDate, order: u32) -> String {
+    format!(
+        "{:02}{:02}-{:03}",
+        buddhist_year(issue_date.year()) % 100,
+        issue_date.month(),
+        order
+    )
+}
+
+/// บิลที่คำนวณแล้ว สำหรับส่งออกให้โปรแกรมอื่น
+#[derive(Debug, Serialize)]
+pub struct ComputedBill<'a> {
+    pub bill_number: String,
+    pub for_month: &'a str,
+    #[serde(flatten)]
+    pub record: &'a BillRecord,
+    pub units_cost: u32,
+}
+
+impl<'a> ComputedBill<'a> {
+    /// บิลที่คำนวณแล้วพร้อมเลขที่บิล สำหรับบันทึกเป็น JSON บัญชีรายรับ และส่งให้โปรแกรมอื่น
+    pub fn from_records(
+        records: &'a [BillRecord],
+        for_month: &'a str,
+        issue_date: NaiveDate,
+    ) -> Vec<Self> {
+        records
+            .iter()
+            .map(|record| ComputedBill {
+                bill_number: bill_number(issue_date, record.order),
+                for_month,
+                record,
+                units_cost: record.units_cost(),
+            })
+            .collect()
+    }
+}
+
+/// ยอดรวมของบิลทั้งเดือน
+/// เก็บเป็น u64 เพราะผลรวมของบิลหลายใบเกินขอบเขตของ u32 ได้ แม้ยอดของแต่ละใบจะไม่เกิน
+#[derive(Debug, Default, Serialize)]
+pub struct BillSummary {
+    pub bill_count: usize,
+    pub total_units: u64,
+    pub total_maintenance_fee: u64,
+    pub total_units_cost: u64,
+    pub total_amount: u64,
+}
+
+impl BillSummary {
+    pub fn from_records<'a>(records: impl IntoIterator<Item = &'a BillRecord>) -> Self {
+        records
+            .into_iter()
+            .fold(BillSummary::default(), |mut summary, record| {
+                summary.bill_count += 1;
+                summary.total_units += u64::from(record.units);
+                summary.total_maintenance_fee += u64::from(record.maintenance_fee);
+                summary.total_units_cost += u64::from(record.units_cost());
+                summary.total_amount += u64::from(record.total_amount);
+                summary
+            })
+    }
+}
+
+/// หัวคอลัมน์ของไฟล์ CSV/Excel ที่ BillRecord อ่านได้ เรียงตามไฟล์ตัวอย่าง
+/// ต้องตรงกับ `rename(deserialize = ...)` ของ BillRecord (ตรวจไว้ในเทสต์ของ csv_util)
+pub const CSV_COLUMNS: [&str; 10] = [
+    "ลำดับ",
+    "เลขมิเตอร์",
+    "ชื่อ",
+    "เลขอ่านครั้งก่อน",
+    "เลขอ่านครั้งหลัง",
+    "หน่วย",
+    "20",
+    "หนวยละ",
+    "จำนวน",
+    "โซน",
+];
+
+/// คอลัมน์ที่เป็นตัวเลข (ชื่อคอลัมน์, ต้องกรอกหรือไม่)
+pub const NUMBER_COLUMNS: [(&str, bool); 7] = [
+    ("ลำดับ", true),
+    ("เลขอ่านครั้งก่อน", false),
+    ("เลขอ่านครั้งหลัง", true),
+    ("หน่วย", false),
+    ("20", true),
+    ("หนวยละ", true),
+    ("จำนวน", false),
+];
+
+// ตัวเลขจากสเปรดชีตหรือที่พิมพ์ด้วยมือ เช่น "๑๒๕๐", "1,250", " 1250 ", "1250.0"
+pub fn parse_number(text: &str) -> Result<Option<u32>, String> {
+    let normalized: String = from_thai_digits(text)
+        .chars()
+        .filter(|c| *c != ',' && !c.is_whitespace())
+        .collect();
+    if normalized.is_empty() {
+        return Ok(None);
+    }
+
+    // ตัดทศนิยมที่เป็นศูนย์ทิ้ง (1250.0 -> 1250)
+    let integer = match normalized.split_once('.') {
+        Some((integer, fraction)) if fraction.chars().all(|c| c == '0') => integer,
+        Some(_) => return Err("ต้องเป็นจำนวนเต็ม".to_string()),
+        None => normalized.as_str(),
+    };
+    integer
+        .parse::<u32>()
+        .map(Some)
+        .map_err(|_| "ไม่ใช่ตัวเลข".to_string())
+}
+
+struct NumberVisitor;
+
+impl<'de> Visitor<'de> for NumberVisitor {
+    type Value = Option<u32>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("ตัวเลขจำนวนเต็ม")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        u32::try_from(value)
+            .map(Some)
+            .map_err(|_| E::custom(format!("{} มากเกินไป", value)))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        u32::try_from(value)
+            .map(Some)
+            .map_err(|_| E::custom(format!("{} ต้องไม่ติดลบ", value)))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+        self.visit_str(&value.to_string())
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        parse_number(value).map_err(E::custom)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+}
+
+fn thai_number<'de, D>(deserializer: D) -> Result<u32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer
+        .deserialize_any(NumberVisitor)?
+        .ok_or_else(|| de::Error::custom("ไม่ได้กรอกข้อมูล"))
+}
+
+// ช่องว่างใน CSV หรือ null ใน JSON คือไม่ได้กรอก
+fn optional_number<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(NumberVisitor)
+}
+
+// ช่องว่างใน CSV ให้ถือว่าเป็น 0
+fn empty_as_zero<'de, D>(deserializer: D) -> Result<u32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(deserializer.deserialize_any(NumberVisitor)?.unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(
+        meter_number: &str,
+        previous_reading: Option<u32>,
+        current_reading: u32,
+    ) -> BillRecord {
+        BillRecord {
+            order: 1,
+            meter_number: meter_number.to_string(),
+            name: "นายสมชาย".to_string(),
+            previous_reading,
+            current_reading,
+            units: 0,
+            maintenance_fee: 20,
+            rate_per_unit: 2,
+            total_amount: 0,
+            zone: String::new(),
+        }
+    }
+
+    #[test]
+    fn complete_fills_blank_units_and_total() {
+        let mut bill = record("M001", Some(1200), 1250);
+        bill.complete().unwrap();
+        assert_eq!((bill.units, bill.total_amount), (50, 120));
+    }
+
+    #[test]
+    fn complete_keeps_typed_units_when_total_is_blank() {
+        let mut bill = record("M001", Some(1200), 1250);
+        bill.units = 45;
+        bill.complete().unwrap();
+        assert_eq!((bill.units, bill.total_amount), (45, 110));
+    }
+
+    #[test]
+    fn complete_rejects_a_missing_previous_reading() {
+        let mut bill = record("M001", None, 1250);
+        assert!(bill.complete().is_err());
+        assert_eq!(bill.units, 0);
+
+        // เลขอ่าน 0 ที่กรอกมา (มิเตอร์ใหม่) ไม่ใช่ช่องว่าง
+        let mut new_meter = record("M001", Some(0), 30);
+        new_meter.complete().unwrap();
+        assert_eq!(new_meter.units, 30);
+    }
+
+    #[test]
+    fn blank_previous_reading_is_none_and_zero_is_kept() {
+        let headers = csv::StringRecord::from(vec![
+            "ลำดับ",
+            "เลขมิเตอร์",
+            "ชื่อ",
+            "เลขอ่านครั้งก่อน",
+            "เลขอ่านครั้งหลัง",
+            "20",
+            "หนวยละ",
+        ]);
+        let parse = |previous: &str| {
+            csv::StringRecord::from(vec!["1", "M001", "ก", previous, "30", "20", "2"])
+                .deserialize::<BillRecord>(Some(&headers))
+                .unwrap()
+                .previous_reading
+        };
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("0"), Some(0));
+        assert_eq!(parse("๑๒"), Some(12));
+    }
+
+    #[test]
+    fn summary_totals_do_not_overflow_u32() {
+        let mut bill = record("M001", Some(0), 0);
+        bill.total_amount = u32::MAX;
+        bill.units = u32::MAX;
+        let summary = BillSummary::from_records([&bill, &bill]);
+        assert_eq!(summary.bill_count, 2);
+        assert_eq!(summary.total_amount, 2 * u64::from(u32::MAX));
+        assert_eq!(summary.total_units, 2 * u64::from(u32::MAX));
+    }
+
+    #[test]
+    fn numbers_are_read_as_typed_in_spreadsheets() {
+        for (text, number) in [
+            ("1250", 1250),
+            ("1,250", 1250),
+            (" 1250 ", 1250),
+            ("1250.0", 1250),
+            ("1250.", 1250),
+            ("๑๒๕๐", 1250),
+            ("๑,๒๕๐.๐๐", 1250),
+            ("0", 0),
+            ("4294967295", u32::MAX),
+        ] {
+            assert_eq!(parse_number(text), Ok(Some(number)), "{:?}", text);
+        }
+        for text in ["", "   ", "\t"] {
+            assert_eq!(parse_number(text), Ok(None), "{:?}", text);
+        }
+        for text in ["12.5", "1250.01", "-5", "abc", ".", "4294967296", "12 หน่วย"] {
+            assert!(parse_number(text).is_err(), "{:?}", text);
+        }
+    }
 }
@@ -0,0 +1,618 @@
+use crate::delivery::{DeliveryLog, DeliveryReport};
+use crate::error::Error;
+use crate::log;
+use crate::model::{bill_number, BillRecord};
+use crate::pdf_util::BillOptions;
+use crate::promptpay::{self, PromptPayId};
+use crate::thai_date::{format_date, MonthName};
+use crate::Result;
+use base64::Engine;
+use chrono::{Days, NaiveDate};
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{CONTENT_TYPE, RETRY_AFTER};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+/// ที่อยู่ LINE Messaging API สำหรับส่งข้อความถึงผู้ใช้ทีละคน
+pub const LINE_PUSH_URL: &str = "https://api.line.me/v2/bot/message/push";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+// รอก่อนส่งซ้ำครั้งแรก ครั้งต่อไปรอนานขึ้นเท่าตัว
+const RETRY_DELAY: Duration = if cfg!(test) {
+    Duration::from_millis(10)
+} else {
+    Duration::from_secs(1)
+};
+// Retry-After ที่นานกว่านี้จะรอแค่นี้
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(300);
+// ตอนทดลองส่ง ข้อความยาว (เช่นภาพ base64) จะแสดงแค่ความยาว
+const DRY_RUN_TEXT_LIMIT: usize = 500;
+
+/// ช่องทางแจ้งบิล
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum NotifyChannel {
+    /// LINE Messaging API (ต้องมี LINE user ID ของผู้ใช้น้ำ)
+    Line,
+    /// ส่ง JSON ไปยัง webhook ที่กำหนด เช่น LINE bot หรือระบบอื่นของหมู่บ้าน
+    Webhook,
+}
+
+/// QR พร้อมเพย์ของบิล
+#[derive(Debug, Clone)]
+pub struct PaymentQr {
+    /// ข้อความใน QR ตามมาตรฐาน Thai QR Payment
+    pub payload: String,
+    /// ภาพ QR (PNG)
+    pub png: Vec<u8>,
+    /// ลิงก์ของภาพ QR ที่เปิดจากภายนอกได้ (LINE ต้องใช้ลิงก์ https)
+    pub url: Option<String>,
+}
+
+/// ข้อมูลการแจ้งบิลของผู้ใช้น้ำหนึ่งราย
+#[derive(Debug, Clone)]
+pub struct BillNotice {
+    /// ผู้รับ เช่น LINE user ID (จากไฟล์รายชื่อผู้รับ)
+    pub recipient: Option<String>,
+    pub meter_number: String,
+    pub name: String,
+    pub for_month: String,
+    pub bill_number: String,
+    pub units: u32,
+    pub amount: u32,
+    pub due_date: NaiveDate,
+    /// ข้อความแจ้งบิลภาษาไทย
+    pub message: String,
+    pub qr: Option<PaymentQr>,
+}
+
+/// คำขอ HTTP ที่จะส่งไปยังปลายทาง
+#[derive(Debug, Clone)]
+pub struct NotifyRequest {
+    pub url: String,
+    /// ส่งเป็น `Authorization: Bearer ...`
+    pub token: Option<String>,
+    pub body: Value,
+    /// ส่วนหัวที่ใช้ส่งคีย์กันส่งซ้ำ (คงที่ต่อบิลและผู้รับ) เช่น X-Line-Retry-Key
+    pub retry_key_header: Option<&'static str>,
+}
+
+/// ตัวแปลงการแจ้งบิลเป็นคำขอ HTTP ของแต่ละช่องทาง เพิ่มช่องทางใหม่ได้โดย implement trait นี้
+pub trait Notifier {
+    /// ชื่อช่องทาง สำหรับบันทึกการทำงาน
+    fn name(&self) -> &'static str;
+
+    /// คำขอที่จะส่งสำหรับบิลนี้ หรือ `None` ถ้าแจ้งรายนี้ไม่ได้ (เช่น ไม่มี LINE user ID)
+    fn request(&self, notice: &BillNotice) -> Option<NotifyRequest>;
+}
+
+/// ส่งข้อความถึงผู้ใช้น้ำผ่าน LINE Messaging API (push message)
+pub struct LineNotifier {
+    pub url: String,
+    /// channel access token ของ LINE Official Account
+    pub token: String,
+}
+
+impl Notifier for LineNotifier {
+    fn name(&self) -> &'static str {
+        "LINE"
+    }
+
+    fn request(&self, notice: &BillNotice) -> Option<NotifyRequest> {
+        let recipient = notice.recipient.as_ref()?;
+        let mut messages = vec![json!({ "type": "text", "text": notice.message })];
+        // LINE รับภาพจากลิงก์ https เท่านั้น ไม่มีลิงก์จึงส่งเฉพาะข้อความ
+        if let Some(url) = notice.qr.as_ref().and_then(|qr| qr.url.as_ref()) {
+            messages.push(json!({
+                "type": "image",
+                "originalContentUrl": url,
+                "previewImageUrl": url,
+            }));
+        }
+        Some(NotifyRequest {
+            url: self.url.clone(),
+            token: Some(self.token.clone()),
+            body: json!({ "to": recipient, "messages": messages }),
+            retry_key_header: Some("X-Line-Retry-Key"),
+        })
+    }
+}
+
+/// ส่งข้อมูลบิลเป็น JSON ไปยัง webhook (ภาพ QR แนบเป็น base64)
+pub struct JsonWebhookNotifier {
+    pub url: String,
+    pub token: Option<String>,
+}
+
+impl Notifier for JsonWebhookNotifier {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn request(&self, notice: &BillNotice) -> Option<NotifyRequest> {
+        let promptpay = notice.qr.as_ref().map(|qr| {
+            json!({
+                "payload": qr.payload,
+                "image_png_base64": base64::engine::general_purpose::STANDARD.encode(&qr.png),
+                "image_url": qr.url,
+            })
+        });
+        Some(NotifyRequest {
+            url: self.url.clone(),
+            token: self.token.clone(),
+            body: json!({
+                "event": "bill_issued",
+                "recipient": notice.recipient,
+                "meter_number": notice.meter_number,
+                "name": notice.name,
+                "month": notice.for_month,
+                "bill_number": notice.bill_number,
+                "units": notice.units,
+                "amount": notice.amount,
+                "due_date": notice.due_date.format("%Y-%m-%d").to_string(),
+                "message": notice.message,
+                "promptpay": promptpay,
+            }),
+            retry_key_header: Some("Idempotency-Key"),
+        })
+    }
+}
+
+/// ตัวเลือกการแจ้งบิล
+#[derive(Debug, Clone)]
+pub struct NotifySettings {
+    /// บัญชีพร้อมเพย์ที่รับเงิน ถ้าระบุจะแนบ QR ตามยอดเงินของแต่ละบิล
+    pub promptpay: Option<PromptPayId>,
+    /// โฟลเดอร์บันทึกภาพ QR ชื่อไฟล์เป็น <เลขที่บิล>.png
+    pub qr_dir: String,
+    /// ลิงก์ของโฟลเดอร์ภาพ QR บนเว็บ เช่น https://example.com/qr
+    pub qr_base_url: Option<String>,
+    /// จำนวนครั้งที่ส่งซ้ำเมื่อส่งไม่สำเร็จ
+    pub retries: u32,
+    /// แสดงสิ่งที่จะส่งโดยไม่ส่งจริง
+    pub dry_run: bool,
+}
+
+/// ชื่อคอลัมน์ผู้รับ (เช่น LINE user ID) ในไฟล์รายชื่อ (อ่านด้วย csv_util::read_contacts)
+pub const CONTACT_COLUMNS: [&str; 6] = ["ผู้รับ", "ไลน์", "line", "line_id", "user_id", "recipient"];
+
+/// แจ้งบิลของแต่ละรายผ่านช่องทางที่เลือก รายที่ส่งไม่สำเร็จหลังลองซ้ำครบแล้วจะข้ามไปรายต่อไป
+/// บิลที่เคยแจ้งผู้รับเดิมสำเร็จแล้ว (ตาม `delivery_log`) จะไม่แจ้งซ้ำ
+pub fn notify_bills(
+    notifier: &dyn Notifier,
+    records: &[BillRecord],
+    for_month: &str,
+    options: &BillOptions,
+    recipients: &HashMap<String, Vec<String>>,
+    settings: &NotifySettings,
+    delivery_log: &mut DeliveryLog,
+) -> Result<DeliveryReport> {
+    log::log_info(&format!(
+        "แจ้งบิลทาง {} {} ราย{}",
+        notifier.name(),
+        records.len(),
+        if settings.dry_run {
+            " (ทดลอง ไม่ส่งจริง)"
+        } else {
+            ""
+        }
+    ));
+    let client = Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+    let mut report = DeliveryReport::default();
+    for record in records {
+        let notice = bill_notice(record, for_month, options, settings)?;
+        // มิเตอร์ที่ไม่มีในรายชื่อยังส่งได้ถ้าช่องทางไม่ต้องการผู้รับ (เช่น webhook)
+        let targets = match recipients.get(record.meter_number.trim()) {
+            Some(list) if !list.is_empty() => list.iter().cloned().map(Some).collect(),
+            _ => vec![None],
+        };
+        for target in targets {
+            let notice = BillNotice {
+                recipient: target,
+                ..notice.clone()
+            };
+            let Some(request) = notifier.request(&notice) else {
+                log::log_debug(&format!("ไม่มีผู้รับของมิเตอร์ {}", record.meter_number));
+                report.skipped += 1;
+                continue;
+            };
+            let recipient = notice.recipient.as_deref().unwrap_or(&request.url);
+            if delivery_log.already_sent(for_month, &notice.bill_number, recipient) {
+                log::log_info(&format!(
+                    "บิล {} เคยแจ้ง {} แล้ว",
+                    notice.bill_number, recipient
+                ));
+                report.skipped += 1;
+                continue;
+            }
+            if settings.dry_run {
+                log::log_info(&format!(
+                    "[ทดลอง] บิล {} -> {} {}",
+                    notice.bill_number,
+                    request.url,
+                    shorten_strings(request.body)
+                ));
+                report.dry_run += 1;
+                continue;
+            }
+            let retry_key = retry_key(for_month, &notice.bill_number, recipient);
+            let result = post_with_retry(&client, &request, &retry_key, settings.retries);
+            match &result {
+                Ok(()) => {
+                    log::log_info(&format!(
+                        "แจ้งบิล {} ถึง {} แล้ว",
+                        notice.bill_number, recipient
+                    ));
+                    report.sent += 1;
+                }
+                Err(error) => {
+                    log::log_error(&format!(
+                        "แจ้งบิล {} ถึง {} ไม่สำเร็จ: {}",
+                        notice.bill_number, recipient, error
+                    ));
+                    report.failed += 1;
+                }
+            }
+            delivery_log.record(
+                for_month,
+                &notice.bill_number,
+                &record.meter_number,
+                recipient,
+                &result,
+            )?;
+        }
+    }
+    log::log_info(&format!(
+        "แจ้งบิลสำเร็จ {} รายการ ไม่สำเร็จ {} รายการ ข้าม {} รายการ{}",
+        report.sent,
+        report.failed,
+        report.skipped,
+        if settings.dry_run {
+            format!(" ทดลองส่ง {} รายการ", report.dry_run)
+        } else {
+            String::new()
+        }
+    ));
+    Ok(report)
+}
+
+// ข้อมูลการแจ้งบิลของมิเตอร์หนึ่งราย ยังไม่ระบุผู้รับ
+fn bill_notice(
+    record: &BillRecord,
+    for_month: &str,
+    options: &BillOptions,
+    settings: &NotifySettings,
+) -> Result<BillNotice> {
+    let number = bill_number(options.issue_date, record.order);
+    let due_date = options.issue_date + Days::new(options.due_days.into());
+    let qr = match &settings.promptpay {
+        Some(id) => Some(payment_qr(id, record.total_amount, &number, settings)?),
+        None => None,
+    };
+    let mut message = format!(
+        "ค่าน้ำประปา หมู่ 3 ประจำเดือน {}\nเลขที่บิล {}\n{} (มิเตอร์ {})\nใช้น้ำ {} หน่วย\nยอดชำระ {} บาท\nกรุณาชำระภายในวันที่ {}",
+        for_month,
+        number,
+        record.name,
+        record.meter_number,
+        record.units,
+        record.total_amount,
+        format_date(due_date, MonthName::Long, options.thai_digits)
+    );
+    if qr.is_some() {
+        message.push_str("\nสแกน QR พร้อมเพย์เพื่อชำระเงินได้ทันที");
+    }
+    Ok(BillNotice {
+        recipient: None,
+        meter_number: record.meter_number.clone(),
+        name: record.name.clone(),
+        for_month: for_month.to_string(),
+        bill_number: number,
+        units: record.units,
+        amount: record.total_amount,
+        due_date,
+        message,
+        qr,
+    })
+}
+
+// สร้างภาพ QR ของบิลและบันทึกลงโฟลเดอร์ (นำโฟลเดอร์นี้ขึ้นเว็บเพื่อให้ LINE แสดงภาพได้)
+// ตอนทดลองส่งสร้างไว้ในหน่วยความจำเท่านั้น ไม่เขียนไฟล์
+fn payment_qr(
+    id: &PromptPayId,
+    amount: u32,
+    number: &str,
+    settings: &NotifySettings,
+) -> Result<PaymentQr> {
+    let payload = promptpay::payload(id, amount);
+    let png = promptpay::qr_png(&payload)?;
+    let file_name = format!("{}.png", number);
+    if !settings.dry_run {
+        fs::create_dir_all(&settings.qr_dir)?;
+        fs::write(Path::new(&settings.qr_dir).join(&file_name), &png)?;
+    }
+    let url = settings
+        .qr_base_url
+        .as_ref()
+        .map(|base| format!("{}/{}", base.trim_end_matches('/'), file_name));
+    Ok(PaymentQr { payload, png, url })
+}
+
+// ส่งคำขอ ถ้าเชื่อมต่อไม่ได้ หรือปลายทางตอบ 429/5xx จะรอแล้วส่งซ้ำ ส่วน 4xx อื่นถือว่าผิดที่ข้อมูล ไม่ส่งซ้ำ
+// ทุกครั้งส่ง retry_key เดิม ปลายทางที่เคยรับคำขอนี้แล้วจะตอบ 409 ซึ่งถือว่าส่งสำเร็จ
+fn post_with_retry(
+    client: &Client,
+    request: &NotifyRequest,
+    retry_key: &str,
+    retries: u32,
+) -> Result<()> {
+    let body = serde_json::to_vec(&request.body)?;
+    let mut attempt = 0;
+    loop {
+        let mut builder = client
+            .post(&request.url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.clone());
+        if let Some(token) = &request.token {
+            builder = builder.bearer_auth(token);
+        }
+        if let Some(header) = request.retry_key_header {
+            builder = builder.header(header, retry_key);
+        }
+        let mut delay = RETRY_DELAY * 2u32.pow(attempt);
+        let error = match builder.send() {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response)
+                if response.status() == StatusCode::CONFLICT
+                    && request.retry_key_header.is_some() =>
+            {
+                log::log_info(&format!("ปลายทางเคยรับคำขอ {} แล้ว", retry_key));
+                return Ok(());
+            }
+            Ok(response) => {
+                let status = response.status();
+                if let Some(seconds) = retry_after(&response) {
+                    delay = Duration::from_secs(seconds).min(MAX_RETRY_AFTER);
+                }
+                let detail = response.text().unwrap_or_default();
+                let error = Error::Network(format!("ปลายทางตอบ {}: {}", status, detail.trim()));
+                if !is_retryable(status) {
+                    return Err(error);
+                }
+                error
+            }
+            Err(error) => Error::from(error),
+        };
+        if attempt >= retries {
+            return Err(error);
+        }
+        attempt += 1;
+        log::log_warn(&format!(
+            "{} จะลองส่งซ้ำครั้งที่ {} ใน {} วินาที",
+            error,
+            attempt,
+            delay.as_secs()
+        ));
+        thread::sleep(delay);
+    }
+}
+
+// ระยะเวลาที่ปลายทางขอให้รอ (Retry-After เป็นจำนวนวินาที) มักมากับคำตอบ 429
+fn retry_after(response: &Response) -> Option<u64> {
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+// คีย์กันส่งซ้ำรูปแบบ UUID ที่ได้จากประจำเดือน เลขที่บิลและผู้รับ รันซ้ำกี่ครั้งก็ได้คีย์เดิม
+// (LINE รับเฉพาะรูปแบบ UUID) ใช้ FNV-1a สองชุดต่อกันเป็น 128 บิต
+fn retry_key(for_month: &str, bill_number: &str, recipient: &str) -> String {
+    let text = format!("{}\n{}\n{}", for_month, bill_number, recipient);
+    let fnv = |seed: u64| {
+        text.bytes().fold(seed, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01B3)
+        })
+    };
+    let bits =
+        (u128::from(fnv(0xCBF2_9CE4_8422_2325)) << 64) | u128::from(fnv(0x8422_2325_CBF2_9CE4));
+    // รุ่น 8 (UUID ที่กำหนดวิธีสร้างเอง) และ variant ตาม RFC 9562
+    let bits = (bits & !(0xF << 76)) | (0x8 << 76);
+    let bits = (bits & !(0x3 << 62)) | (0x2 << 62);
+    let hex = format!("{:032x}", bits);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+// ย่อข้อความยาวใน JSON สำหรับแสดงตอนทดลองส่ง
+fn shorten_strings(value: Value) -> Value {
+    match value {
+        Value::String(text) if text.chars().count() > DRY_RUN_TEXT_LIMIT => {
+            Value::String(format!("<{} ตัวอักษร>", text.chars().count()))
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(shorten_strings).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key, shorten_strings(value)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    // ปลายทางจำลอง ตอบตามรหัสสถานะที่กำหนดทีละคำขอ คืนส่วนหัวและเนื้อหาของคำขอที่ได้รับ
+    fn mock_server(statuses: Vec<u16>) -> (String, thread::JoinHandle<Vec<(String, Value)>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let mut received = Vec::new();
+            for status in statuses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut headers = String::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    headers.push_str(&line.to_ascii_lowercase());
+                }
+                let length = headers
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .map_or(0, |value| value.trim().parse().unwrap());
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                received.push((headers, serde_json::from_slice(&body).unwrap()));
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 {} X\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{{}}",
+                    status
+                )
+                .unwrap();
+            }
+            received
+        });
+        (url, handle)
+    }
+
+    fn record() -> BillRecord {
+        BillRecord {
+            order: 1,
+            meter_number: "M001".to_string(),
+            name: "สมชาย".to_string(),
+            previous_reading: Some(100),
+            current_reading: 130,
+            units: 30,
+            maintenance_fee: 20,
+            rate_per_unit: 2,
+            total_amount: 80,
+            zone: String::new(),
+        }
+    }
+
+    fn settings(retries: u32) -> NotifySettings {
+        NotifySettings {
+            promptpay: Some(PromptPayId::Phone("0812345678".to_string())),
+            qr_dir: std::env::temp_dir()
+                .join("papa_moo_3_qr")
+                .display()
+                .to_string(),
+            qr_base_url: None,
+            retries,
+            dry_run: false,
+        }
+    }
+
+    fn status_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!(
+            "papa_moo_3_notify_{}_{}.csv",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_file(&path);
+        path.display().to_string()
+    }
+
+    fn notify(url: &str, retries: u32, delivery_log: &mut DeliveryLog) -> Result<DeliveryReport> {
+        let notifier = JsonWebhookNotifier {
+            url: url.to_string(),
+            token: None,
+        };
+        let recipients = HashMap::from([("M001".to_string(), vec!["U1".to_string()])]);
+        notify_bills(
+            &notifier,
+            &[record()],
+            "ต.ค. 68",
+            &BillOptions::default(),
+            &recipients,
+            &settings(retries),
+            delivery_log,
+        )
+    }
+
+    #[test]
+    fn retries_server_errors_with_the_same_key() {
+        let (url, server) = mock_server(vec![500, 503, 200]);
+        let mut delivery_log = DeliveryLog::load(&status_path("retry")).unwrap();
+        let report = notify(&url, 3, &mut delivery_log).unwrap();
+        assert_eq!((report.sent, report.failed), (1, 0));
+
+        let received = server.join().unwrap();
+        assert_eq!(received.len(), 3);
+        let keys: Vec<&str> = received
+            .iter()
+            .map(|(headers, _)| {
+                headers
+                    .lines()
+                    .find_map(|line| line.strip_prefix("idempotency-key:"))
+                    .unwrap()
+                    .trim()
+            })
+            .collect();
+        assert!(keys.iter().all(|key| *key == keys[0]));
+        assert_eq!(received[0].1["recipient"], "U1");
+        assert_eq!(received[0].1["amount"], 80);
+
+        // รันซ้ำแล้วไม่ส่งบิลที่สำเร็จไปแล้ว
+        let report = notify(&url, 3, &mut delivery_log).unwrap();
+        assert_eq!((report.sent, report.skipped), (0, 1));
+    }
+
+    #[test]
+    fn does_not_retry_client_errors() {
+        let (url, server) = mock_server(vec![400]);
+        let path = status_path("client_error");
+        let mut delivery_log = DeliveryLog::load(&path).unwrap();
+        let report = notify(&url, 3, &mut delivery_log).unwrap();
+        assert_eq!((report.sent, report.failed), (0, 1));
+        assert_eq!(server.join().unwrap().len(), 1);
+        assert!(report.into_result("แจ้งบิล", &path).is_err());
+    }
+
+    #[test]
+    fn gives_up_after_retries() {
+        let (url, server) = mock_server(vec![500, 500]);
+        let mut delivery_log = DeliveryLog::load(&status_path("give_up")).unwrap();
+        let report = notify(&url, 1, &mut delivery_log).unwrap();
+        assert_eq!(report.failed, 1);
+        assert_eq!(server.join().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn retry_key_is_a_stable_uuid() {
+        let key = retry_key("ต.ค. 68", "6810-001", "U1");
+        assert_eq!(key, retry_key("ต.ค. 68", "6810-001", "U1"));
+        assert_ne!(key, retry_key("ต.ค. 68", "6810-002", "U1"));
+        // บิล ก.ย. ที่ออกในเดือน ต.ค. ได้เลขที่บิลเดียวกัน ต้องได้คีย์คนละตัว
+        assert_ne!(key, retry_key("ก.ย. 68", "6810-001", "U1"));
+        assert_eq!(key.len(), 36);
+        assert_eq!(&key[14..15], "8");
+    }
+}
@@ -1,17 +1,361 @@
-use super::drawing::{draw_bill_split_line, draw_line, draw_vetical_line};
+use super::drawing::{draw_bill_split_line, draw_dashed_line, draw_line, draw_vetical_line};
 use super::font_util::find_thai_font;
+use crate::error::Error;
 use crate::log;
-use crate::model::BillRecord;
+use crate::model::{bill_number, BillRecord};
+use crate::thai_date::{format_date, MonthName};
+use crate::usage_chart::UsageHistory;
+use crate::Result;
+use chrono::{Days, NaiveDate, NaiveTime};
 use printpdf::*;
-use std::error::Error;
-use std::fs::File;
-use std::io::BufWriter;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
 
+const FONT_SIZE_NORMAL: f32 = 12.0;
+const FONT_SIZE_SUBTITLE: f32 = 14.0;
+const FONT_SIZE_TITLE: f32 = 16.0;
+
+/// รูปแบบการพิมพ์ต้นฉบับ/สำเนา
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum CopyMode {
+    /// พิมพ์ใบเดียว (ไม่มีสำเนา)
+    #[default]
+    Single,
+    /// ต้นฉบับและสำเนาของลูกค้ารายเดียวกันอยู่บนแผ่นเดียวกัน (บน/ล่าง)
+    SameSheet,
+    /// แยกต้นฉบับและสำเนาเป็นไฟล์ PDF คนละไฟล์
+    Separate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BillCopy {
+    Customer,
+    Office,
+}
+
+impl BillCopy {
+    pub fn label(self) -> &'static str {
+        match self {
+            BillCopy::Customer => "ต้นฉบับ (ลูกค้า)",
+            BillCopy::Office => "สำเนา (สำนักงาน)",
+        }
+    }
+
+    fn file_suffix(self) -> &'static str {
+        match self {
+            BillCopy::Customer => "customer",
+            BillCopy::Office => "office",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct BillOptions {
+    pub copy_mode: CopyMode,
+    /// พิมพ์ส่วนต่อท้ายสำหรับฉีกเก็บไว้เป็นหลักฐานการรับเงิน
+    pub payment_stub: bool,
+    /// จำนวนวันนับจากวันออกบิลถึงวันครบกำหนดชำระ
+    pub due_days: u32,
+    /// วันออกบิล (ใช้คำนวณเลขที่บิลและวันครบกำหนดชำระ และเป็นวันที่ในข้อมูลเอกสาร PDF)
+    pub issue_date: NaiveDate,
+    /// พิมพ์ซ้ำจากทะเบียนบิล จะมีคำว่า "พิมพ์ซ้ำ" กำกับไว้
+    pub reprint: bool,
+    /// พิมพ์วันที่เป็นเลขไทย
+    pub thai_digits: bool,
+    /// กราฟหน่วยที่ใช้ย้อนหลังใต้แถวเลขอ่านมิเตอร์ ข้างตารางค่าน้ำ
+    pub usage_chart: Option<UsageHistory>,
+    /// เลขมิเตอร์ที่ต้องพิมพ์คำเตือนให้ตรวจสอบรอยรั่ว
+    pub leak_notices: HashSet<String>,
+}
+
+/// ค่าเริ่มต้นใช้วันนี้เป็นวันออกบิล PDF ที่สร้างต่างวันจึงไม่เหมือนกัน
+/// ถ้าต้องการไฟล์เหมือนเดิมทุกไบต์ให้กำหนด `issue_date` เอง
+impl Default for BillOptions {
+    fn default() -> Self {
+        BillOptions {
+            copy_mode: CopyMode::Single,
+            payment_stub: false,
+            due_days: 15,
+            issue_date: chrono::Local::now().date_naive(),
+            reprint: false,
+            thai_digits: false,
+            usage_chart: None,
+            leak_notices: HashSet::new(),
+        }
+    }
+}
+
+// ข้อมูลที่ใช้ร่วมกันทุกใบในการออกบิลรอบนี้
+struct BillContext<'a> {
+    for_month: &'a str,
+    issue_date: NaiveDate,
+    due_date: NaiveDate,
+    options: &'a BillOptions,
+}
+
+impl BillContext<'_> {
+    /// วันที่แบบไทย เช่น 05 ต.ค. 2568
+    fn date_text(&self, date: NaiveDate) -> String {
+        format_date(date, MonthName::Short, self.options.thai_digits)
+    }
+}
+
+struct BillFonts {
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    italic: IndirectFontRef,
+}
+
+// ใบเสร็จหนึ่งใบบนหน้ากระดาษ
+struct BillSlip<'a> {
+    bill: &'a BillRecord,
+    bill_number: String,
+    copy: Option<BillCopy>,
+}
+
+impl<'a> BillContext<'a> {
+    fn new(for_month: &'a str, options: &'a BillOptions) -> Self {
+        BillContext {
+            for_month,
+            issue_date: options.issue_date,
+            due_date: options.issue_date + Days::new(options.due_days.into()),
+            options,
+        }
+    }
+
+    fn slip(&self, bill: &'a BillRecord, copy: Option<BillCopy>) -> BillSlip<'a> {
+        BillSlip {
+            bill,
+            bill_number: bill_number(self.issue_date, bill.order),
+            copy,
+        }
+    }
+}
+
+// ใบเสร็จทุกใบของบิลเหล่านี้ที่มีป้ายฉบับตาม `copy`
+fn slips_for_copy<'a>(
+    records: &'a [BillRecord],
+    context: &BillContext<'a>,
+    copy: Option<BillCopy>,
+) -> Vec<BillSlip<'a>> {
+    records
+        .iter()
+        .map(|bill| context.slip(bill, copy))
+        .collect()
+}
+
+// ต้นฉบับและสำเนาของลูกค้ารายเดียวกันอยู่ติดกัน (บน/ล่างของแผ่นเดียวกัน)
+fn same_sheet_slips<'a>(records: &'a [BillRecord], context: &BillContext<'a>) -> Vec<BillSlip<'a>> {
+    log::log_info("พิมพ์ต้นฉบับและสำเนาบนแผ่นเดียวกัน");
+    records
+        .iter()
+        .flat_map(|bill| {
+            [
+                context.slip(bill, Some(BillCopy::Customer)),
+                context.slip(bill, Some(BillCopy::Office)),
+            ]
+        })
+        .collect()
+}
+
+/// สร้างไฟล์ PDF ใบเสร็จ คืนค่าไฟล์ที่เขียนจริง
+/// แบบแยกต้นฉบับและสำเนาจะไม่เขียน `output_path` แต่เขียน `<ชื่อ>_customer.pdf` และ `<ชื่อ>_office.pdf`
 pub fn create_pdf(
     records: &[BillRecord],
     output_path: &str,
     for_month: &str,
-) -> Result<(), Box<dyn Error>> {
+    options: &BillOptions,
+) -> Result<Vec<String>> {
+    let context = BillContext::new(for_month, options);
+    match options.copy_mode {
+        CopyMode::Single => Ok(vec![write_bills(
+            &slips_for_copy(records, &context, None),
+            output_path,
+            &context,
+        )?]),
+        CopyMode::SameSheet => Ok(vec![write_bills(
+            &same_sheet_slips(records, &context),
+            output_path,
+            &context,
+        )?]),
+        CopyMode::Separate => {
+            log::log_info("แยกไฟล์ต้นฉบับและสำเนา");
+            [BillCopy::Customer, BillCopy::Office]
+                .into_iter()
+                .map(|copy| {
+                    let slips = slips_for_copy(records, &context, Some(copy));
+                    write_bills(&slips, &copy_output_path(output_path, copy), &context)
+                })
+                .collect()
+        }
+    }
+}
+
+/// สร้าง PDF ใบเสร็จในหน่วยความจำ (สำหรับส่งทางเว็บหรือโปรแกรมอื่นโดยไม่เขียนไฟล์)
+/// แบบแยกต้นฉบับและสำเนา จะได้ต้นฉบับทุกใบก่อนแล้วตามด้วยสำเนาทุกใบในไฟล์เดียว
+pub fn create_pdf_bytes(
+    records: &[BillRecord],
+    for_month: &str,
+    options: &BillOptions,
+) -> Result<Vec<u8>> {
+    let context = BillContext::new(for_month, options);
+    let slips = match options.copy_mode {
+        CopyMode::Single => slips_for_copy(records, &context, None),
+        CopyMode::SameSheet => same_sheet_slips(records, &context),
+        CopyMode::Separate => [BillCopy::Customer, BillCopy::Office]
+            .into_iter()
+            .flat_map(|copy| slips_for_copy(records, &context, Some(copy)))
+            .collect(),
+    };
+    render_bills(&slips, &context)
+}
+
+/// สร้าง PDF แยกไฟล์ละหนึ่งบิล ตั้งชื่อไฟล์ตามรูปแบบ `name_pattern` คืนค่าไฟล์ที่เขียนจริง (ดู `create_pdf`)
+/// ใช้ {month} {order} {meter} {name} แทนค่าของแต่ละบิลได้ เช่น `bills/{month}/{order}-{meter}.pdf`
+pub fn create_per_customer_pdfs(
+    records: &[BillRecord],
+    name_pattern: &str,
+    for_month: &str,
+    options: &BillOptions,
+) -> Result<Vec<String>> {
+    log::log_info(&format!(
+        "สร้าง PDF แยกรายผู้ใช้น้ำ {} ไฟล์ ตามรูปแบบ {}",
+        records.len(),
+        name_pattern
+    ));
+    // ตรวจชื่อไฟล์ซ้ำก่อนสร้างไฟล์แรก ไม่ให้บิลหลังเขียนทับบิลก่อนหน้า
+    let mut orders: HashMap<String, u32> = HashMap::new();
+    let mut paths = Vec::with_capacity(records.len());
+    for bill in records {
+        let path = per_customer_path(name_pattern, for_month, bill);
+        if let Some(order) = orders.insert(path.clone(), bill.order) {
+            return Err(Error::Validation(format!(
+                "บิลลำดับ {} และ {} ได้ชื่อไฟล์เดียวกัน ({}) ใส่ {{order}} หรือ {{meter}} ในรูปแบบชื่อไฟล์ {}",
+                order, bill.order, path, name_pattern
+            )));
+        }
+        paths.push(path);
+    }
+
+    let mut written = Vec::with_capacity(paths.len());
+    for (bill, path) in records.iter().zip(&paths) {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        written.extend(create_pdf(
+            std::slice::from_ref(bill),
+            path,
+            for_month,
+            options,
+        )?);
+    }
+    Ok(written)
+}
+
+/// ชื่อไฟล์ของบิลตามรูปแบบ `name_pattern` (ดู `create_per_customer_pdfs`)
+pub fn per_customer_path(name_pattern: &str, for_month: &str, bill: &BillRecord) -> String {
+    // ค่าที่แทนลงไปต้องไม่มีตัวอักษรที่ใช้ในชื่อไฟล์ไม่ได้ เช่น / ในชื่อเดือนหรือชื่อคน
+    let clean = |value: &str| -> String {
+        value
+            .trim()
+            .chars()
+            .map(|c| match c {
+                '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+                c => c,
+            })
+            .collect()
+    };
+    name_pattern
+        .replace("{month}", &clean(for_month))
+        .replace("{order}", &format!("{:03}", bill.order))
+        .replace("{meter}", &clean(&bill.meter_number))
+        .replace("{name}", &clean(&bill.name))
+}
+
+/// แปลงเอกสารเป็นไฟล์ PDF ที่ได้ผลเหมือนเดิมทุกครั้งเมื่อข้อมูลเหมือนเดิม
+/// วันที่ในข้อมูลเอกสารใช้ `document_date` แทนเวลาปัจจุบัน และรหัสเอกสาร (/ID) คำนวณจากเนื้อหา
+pub fn pdf_bytes(doc: PdfDocumentReference, document_date: NaiveDate) -> Result<Vec<u8>> {
+    let timestamp = document_date.and_time(NaiveTime::MIN).and_utc().timestamp();
+    let date = OffsetDateTime::from_unix_timestamp(timestamp)
+        .map_err(|error| Error::Pdf(error.to_string()))?;
+    let bytes = doc
+        .with_creation_date(date)
+        .with_mod_date(date)
+        .with_metadata_date(date)
+        .with_document_id(String::new())
+        .save_to_bytes()?;
+
+    // printpdf สุ่มรหัสเอกสารใน trailer ทุกครั้ง จึงเขียนทับด้วยค่าที่ได้จากเนื้อหาเอกสาร
+    let mut document = lopdf::Document::load_mem(&bytes)?;
+    let set_id = |document: &mut lopdf::Document, id: &str| {
+        let id = lopdf::Object::string_literal(id);
+        document
+            .trailer
+            .set("ID", lopdf::Object::Array(vec![id.clone(), id]));
+    };
+    let mut unidentified = Vec::new();
+    set_id(&mut document, "");
+    document.save_to(&mut unidentified)?;
+    set_id(&mut document, &content_id(&unidentified));
+
+    let mut output = Vec::new();
+    document.save_to(&mut output)?;
+    Ok(output)
+}
+
+/// บันทึกไฟล์ PDF โดยไม่ฝังเวลาปัจจุบัน (ดู `pdf_bytes`)
+pub fn save_pdf(
+    doc: PdfDocumentReference,
+    output_path: &str,
+    document_date: NaiveDate,
+) -> Result<()> {
+    let bytes = pdf_bytes(doc, document_date)?;
+    let mut file = BufWriter::new(File::create(output_path)?);
+    file.write_all(&bytes)?;
+    file.flush()?;
+    Ok(())
+}
+
+// รหัส 32 หลักฐานสิบหกจากเนื้อหา (FNV-1a 64 บิต สองรอบด้วยค่าเริ่มต้นต่างกัน)
+fn content_id(bytes: &[u8]) -> String {
+    let fnv = |offset: u64| {
+        bytes.iter().fold(offset, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+    };
+    format!(
+        "{:016x}{:016x}",
+        fnv(0xcbf2_9ce4_8422_2325),
+        fnv(0x6c62_272e_07bb_0142)
+    )
+}
+
+// bills/plumbing.pdf -> bills/plumbing_customer.pdf
+fn copy_output_path(output_path: &str, copy: BillCopy) -> String {
+    let path = Path::new(output_path);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}_{}.pdf", stem, copy.file_suffix()))
+        .to_string_lossy()
+        .into_owned()
+}
+
+// เขียนใบเสร็จลงไฟล์ คืนค่าชื่อไฟล์ที่เขียน
+fn write_bills(slips: &[BillSlip], output_path: &str, context: &BillContext) -> Result<String> {
+    let bytes = render_bills(slips, context)?;
+
+    // บันทึกไฟล์ PDF
+    log::log_info(&format!("กำลังบันทึกไฟล์ PDF: {}", output_path));
+    fs::write(output_path, bytes)?;
+    log::log_info("บันทึกไฟล์ PDF สำเร็จ!");
+    Ok(output_path.to_string())
+}
+
+fn render_bills(slips: &[BillSlip], context: &BillContext) -> Result<Vec<u8>> {
     log::log_info("เริ่มสร้าง PDF...");
 
     // สร้าง PDF document
@@ -24,16 +368,18 @@ pub fn create_pdf(
     );
 
     // ตั้งค่าฟอนต์ภาษาไทย
-    let (font, bold_font, italic_font) = find_thai_font(&doc);
-    let font_size_normal = 12.0;
-    let font_size_subtitle = 14.0;
-    let font_size_title = 16.0;
+    let (regular, bold, italic) = find_thai_font(&doc)?;
+    let fonts = BillFonts {
+        regular,
+        bold,
+        italic,
+    };
 
     // สร้างใบเสร็จ 2 ใบต่อหน้า
-    let chunks: Vec<_> = records.chunks(2).collect();
+    let chunks: Vec<_> = slips.chunks(2).collect();
     log::log_info(&format!("จำนวนหน้าทั้งหมด: {}", chunks.len()));
 
-    for (i, record_pair) in chunks.iter().enumerate() {
+    for (i, slip_pair) in chunks.iter().enumerate() {
         // log::log_info(&format!("กำลังสร้างหน้า {}...", i + 1));
 
         let current_layer = if i == 0 {
@@ -48,7 +394,7 @@ pub fn create_pdf(
         let y_offset_top_bill = Mm(198.0); // ลดลงจาก 195.0
         let y_offset_bottom_bill = Mm(100.0); // ลดลงจาก 105.0
 
-        for (j, bill) in record_pair.iter().enumerate() {
+        for (j, slip) in slip_pair.iter().enumerate() {
             let y_offset = if j == 0 {
                 y_offset_top_bill
             } else {
@@ -60,264 +406,500 @@ pub fn create_pdf(
                 i + 1,
                 y_offset.0
             ));
+            draw_bill(&current_layer, &fonts, slip, y_offset, context);
+        }
+    }
 
-            // เขียนข้อมูลใบเสร็จ
-            log::log_debug(&format!("เขียนข้อมูล: {} - {}", bill.meter_number, bill.name));
-
-            // หัวข้อใหญ่ - ย้ายไปตรงกลาง
-            current_layer.use_text(
-                "การประปาหมู่บ้าน หมู่ 3",
-                font_size_title,
-                Mm(50.0), // ตำแหน่ง X ตรงกลาง (148mm/2 - 24mm)
-                y_offset,
-                &bold_font,
-            );
-
-            // ที่อยู่ - ย้ายไปตรงกลาง
-            current_layer.use_text(
-                "ต.คมบาง อ.เมือง จ.จันทบุรี",
-                font_size_subtitle,
-                Mm(52.0),           // ตำแหน่ง X ตรงกลางเหมือนหัวข้อใหญ่
-                y_offset - Mm(7.0), // ปรับระยะห่าง
-                &bold_font,
-            );
-
-            // วันที่ออกบิล (ตัวอย่าง)
-            current_layer.use_text(
-                "วันออกบิล",
-                font_size_normal,
-                Mm(8.0),             // ตำแหน่ง X
-                y_offset - Mm(26.0), // ปรับระยะห่าง
-                &bold_font,
-            );
-            current_layer.use_text(
-                format!(
-                    "{} {} {}",
-                    chrono::Local::now().format("%d").to_string(),
-                    match chrono::Local::now().format("%m").to_string().as_str() {
-                        "01" => "ม.ค.",
-                        "02" => "ก.พ.",
-                        "03" => "มี.ค.",
-                        "04" => "เม.ย.",
-                        "05" => "พ.ค.",
-                        "06" => "มิ.ย.",
-                        "07" => "ก.ค.",
-                        "08" => "ส.ค.",
-                        "09" => "ก.ย.",
-                        "10" => "ต.ค.",
-                        "11" => "พ.ย.",
-                        "12" => "ธ.ค.",
-                        _ => "",
-                    },
-                    (chrono::Local::now()
-                        .format("%Y")
-                        .to_string()
-                        .parse::<i32>()
-                        .unwrap()
-                        + 543)
-                        .to_string()
-                ),
-                font_size_normal,
-                Mm(30.0),            // ตำแหน่ง X ถัดมา
-                y_offset - Mm(26.0), // ลดระยะห่างลง 20%
-                &italic_font,
-            );
-
-            //เลข ID user
-            current_layer.use_text(
-                "id.",
-                font_size_normal,
-                Mm(8.0),
-                y_offset - Mm(15.0),
-                &bold_font,
-            );
-            current_layer.use_text(
-                &bill.order.to_string(),
-                font_size_normal,
-                Mm(30.5),
-                y_offset - Mm(15.0),
-                &italic_font,
-            );
-
-            // ประจำเดือน (ตัวอย่าง)
-            current_layer.use_text(
-                "ประจำเดือน",
-                font_size_normal,
-                Mm(8.0),             // ตำแหน่ง X ทางขวา
-                y_offset - Mm(20.0), // ลดระยะห่างลง 20%
-                &bold_font,
-            );
-            current_layer.use_text(
-                for_month, // ใช้ค่าคงที่ไปก่อน
-                font_size_normal,
-                Mm(30.5),            // ตำแหน่ง X ถัดมา
-                y_offset - Mm(20.0), // ลดระยะห่างลง 20%
-                &italic_font,
-            );
-
-            // ชื่อ-นามสกุล
-            current_layer.use_text(
-                "ชื่อ-นามสกุล",
-                font_size_normal,
-                Mm(60.0),            // ตำแหน่ง X ถัดมา
-                y_offset - Mm(15.0), // ลดระยะห่างลง 20%
-                &bold_font,
-            );
-            current_layer.use_text(
-                &bill.name,
-                font_size_normal,
-                Mm(90.0),            // ตำแหน่ง X ถัดมา
-                y_offset - Mm(15.0), // ลดระยะห่างลง 20%
-                &italic_font,
-            );
-
-            // หัวตาราง
-            let table_header_y = y_offset - Mm(35.0); // ปรับระยะห่าง
-            current_layer.use_text(
-                "เลขมิเตอร์",
-                font_size_normal,
-                Mm(8.0), // เริ่มต้นที่ตำแหน่งเดียวกับ "วันที่ออกบิล"
-                table_header_y,
-                &bold_font,
-            );
-            current_layer.use_text(
-                "เลขอ่านครั้งหลัง",
-                font_size_normal,
-                Mm(28.0), // ปรับให้มีระยะห่างเพียงพอ
-                table_header_y,
-                &bold_font,
-            );
-            current_layer.use_text(
-                "เลขอ่านครั้งก่อน",
-                font_size_normal,
-                Mm(58.0), // ปรับให้มีระยะห่างเพียงพอ
-                table_header_y,
-                &bold_font,
-            );
-            current_layer.use_text(
-                "จำนวนหน่วย",
-                font_size_normal,
-                Mm(88.0), // ปรับให้มีระยะห่างเพียงพอ
-                table_header_y,
-                &bold_font,
-            );
-            current_layer.use_text(
-                "จำนวนเงิน",
-                font_size_normal,
-                Mm(113.0), // ปรับให้อยู่ในขอบเขต 135
-                table_header_y,
-                &bold_font,
-            );
-
-            // ข้อมูลในตาราง
-            let table_data_y_start = y_offset - Mm(43.0); // ปรับระยะห่าง
-            let table_data_y_line_height = Mm(6.5); // ปรับระยะห่าง
-
-            // แถวข้อมูลมิเตอร์
-            current_layer.use_text(
-                &bill.meter_number,
-                font_size_normal,
-                Mm(8.0), // ตรงกับหัวตาราง "หมายเลขมิเตอร์"
-                table_data_y_start,
-                &font,
-            );
-            current_layer.use_text(
-                &bill.current_reading.to_string(),
-                font_size_normal,
-                Mm(33.0), // ตรงกับหัวตาราง "เลขอ่านครั้งหลัง"
-                table_data_y_start,
-                &font,
-            );
-            current_layer.use_text(
-                &bill.previous_reading.to_string(),
-                font_size_normal,
-                Mm(58.0), // ตรงกับหัวตาราง "เลขอ่านครั้งก่อน"
-                table_data_y_start,
-                &font,
-            );
-
-            // แถวค่าบำรุง
-            current_layer.use_text(
-                "ค่าบำรุง",
-                font_size_normal,
-                Mm(89.0), // ตรงกับหัวตาราง "จำนวนหน่วย"
-                table_data_y_start,
-                &bold_font,
-            );
-            current_layer.use_text(
-                &bill.maintenance_fee.to_string(),
-                font_size_normal,
-                Mm(115.0), // ตรงกับหัวตาราง "จำนวนเงิน"
-                table_data_y_start,
-                &font,
-            );
-
-            // แถวจำนวนหน่วยและเงิน
-            current_layer.use_text(
-                &bill.units.to_string(),
-                font_size_normal,
-                Mm(89.0), // ตรงกับหัวตาราง "จำนวนหน่วย"
-                table_data_y_start - table_data_y_line_height,
-                &font,
-            );
-            // คำนวณค่าหน่วย (หน่วย * หน่วยละ)
-            let units_cost = bill.units * bill.rate_per_unit;
-            current_layer.use_text(
-                &units_cost.to_string(),
-                font_size_normal,
-                Mm(115.0), // ตรงกับหัวตาราง "จำนวนเงิน"
-                table_data_y_start - table_data_y_line_height,
-                &font,
-            );
-
-            // แถวรวม
-            current_layer.use_text(
-                "รวม",
-                font_size_normal,
-                Mm(89.0), // ตรงกับหัวตาราง "จำนวนหน่วย"
-                table_data_y_start - table_data_y_line_height * 2.0,
-                &bold_font,
-            );
-            current_layer.use_text(
-                &bill.total_amount.to_string(),
-                font_size_normal,
-                Mm(115.0), // ตรงกับหัวตาราง "จำนวนเงิน"
-                table_data_y_start - table_data_y_line_height * 2.0,
-                &font,
-            );
-
-            // สามารถเพิ่มการวาดเส้นเพื่อทำตารางได้ที่นี่
-            //เส้นใต้ข้อมูล
-            let split_line = draw_bill_split_line();
-            let id_underline = draw_line(y_offset - Mm(16.0), Mm(30.0), Mm(36.0));
-            let month_underline = draw_line(y_offset - Mm(21.0), Mm(30.0), Mm(38.0));
-            let bill_date_underline = draw_line(y_offset - Mm(27.0), Mm(30.0), Mm(55.0));
-            let name_underline = draw_line(y_offset - Mm(16.0), Mm(84.0), Mm(135.0));
-
-            current_layer.add_line(id_underline);
-            current_layer.add_line(month_underline);
-            current_layer.add_line(bill_date_underline);
-            current_layer.add_line(name_underline);
-            current_layer.add_line(split_line);
-
-            //เส้นตาราง
-            let header_line_top = draw_line(y_offset - Mm(30.0), Mm(6.0), Mm(135.0)); // เส้นบนตาราง จาก 8 ถึง 135
-            let header_line_bottom = draw_line(y_offset - Mm(37.0), Mm(6.0), Mm(135.0)); // เส้นล่างหัวตาราง
-            let middle_line = draw_line(y_offset - Mm(51.0), Mm(6.0), Mm(135.0)); // เส้นล่างตาราง
-            let vertical_line_1 = draw_vetical_line(Mm(87.5), Mm(168.2), Mm(140.0)); // เส้นแนวตั้งระหว่าง "เลขอ่านครั้งก่อน" และ "จำนวนหน่วย" (58+83)/2 = 70.5
-            let vertical_line_2 = draw_vetical_line(Mm(87.0), Mm(70.2), Mm(40.0)); // เส้นแนวตั้งเดิม
-
-            current_layer.add_line(header_line_top);
-            current_layer.add_line(header_line_bottom);
-            current_layer.add_line(middle_line);
-            current_layer.add_line(vertical_line_1);
-            current_layer.add_line(vertical_line_2);
+    pdf_bytes(doc, context.issue_date)
+}
+
+fn draw_bill(
+    layer: &PdfLayerReference,
+    fonts: &BillFonts,
+    slip: &BillSlip,
+    y_offset: Mm,
+    context: &BillContext,
+) {
+    let bill = slip.bill;
+
+    // เขียนข้อมูลใบเสร็จ
+    log::log_debug(&format!("เขียนข้อมูล: {} - {}", bill.meter_number, bill.name));
+
+    // หัวข้อใหญ่ - ย้ายไปตรงกลาง
+    layer.use_text(
+        "การประปาหมู่บ้าน หมู่ 3",
+        FONT_SIZE_TITLE,
+        Mm(50.0), // ตำแหน่ง X ตรงกลาง (148mm/2 - 24mm)
+        y_offset,
+        &fonts.bold,
+    );
+
+    // ที่อยู่ - ย้ายไปตรงกลาง
+    layer.use_text(
+        "ต.คมบาง อ.เมือง จ.จันทบุรี",
+        FONT_SIZE_SUBTITLE,
+        Mm(52.0),           // ตำแหน่ง X ตรงกลางเหมือนหัวข้อใหญ่
+        y_offset - Mm(7.0), // ปรับระยะห่าง
+        &fonts.bold,
+    );
+
+    // วันที่ออกบิล (ตัวอย่าง)
+    layer.use_text(
+        "วันออกบิล",
+        FONT_SIZE_NORMAL,
+        Mm(8.0),             // ตำแหน่ง X
+        y_offset - Mm(26.0), // ปรับระยะห่าง
+        &fonts.bold,
+    );
+    layer.use_text(
+        context.date_text(context.issue_date),
+        FONT_SIZE_NORMAL,
+        Mm(30.0),            // ตำแหน่ง X ถัดมา
+        y_offset - Mm(26.0), // ลดระยะห่างลง 20%
+        &fonts.italic,
+    );
+
+    // ป้ายบอกฉบับ (ต้นฉบับ/สำเนา)
+    if let Some(copy) = slip.copy {
+        layer.use_text(
+            copy.label(),
+            FONT_SIZE_NORMAL,
+            Mm(112.0),
+            y_offset,
+            &fonts.bold,
+        );
+    }
+
+    // บิลที่พิมพ์ซ้ำจากทะเบียน
+    if context.options.reprint {
+        layer.use_text(
+            "พิมพ์ซ้ำ",
+            FONT_SIZE_NORMAL,
+            Mm(112.0),
+            y_offset - Mm(7.0),
+            &fonts.bold,
+        );
+    }
+
+    // เลขที่บิล (ต้นฉบับและสำเนาใช้เลขเดียวกัน)
+    layer.use_text(
+        "เลขที่บิล",
+        FONT_SIZE_NORMAL,
+        Mm(60.0),
+        y_offset - Mm(20.0),
+        &fonts.bold,
+    );
+    layer.use_text(
+        &slip.bill_number,
+        FONT_SIZE_NORMAL,
+        Mm(90.0),
+        y_offset - Mm(20.0),
+        &fonts.italic,
+    );
+
+    //เลข ID user
+    layer.use_text(
+        "id.",
+        FONT_SIZE_NORMAL,
+        Mm(8.0),
+        y_offset - Mm(15.0),
+        &fonts.bold,
+    );
+    layer.use_text(
+        bill.order.to_string(),
+        FONT_SIZE_NORMAL,
+        Mm(30.5),
+        y_offset - Mm(15.0),
+        &fonts.italic,
+    );
+
+    // ประจำเดือน (ตัวอย่าง)
+    layer.use_text(
+        "ประจำเดือน",
+        FONT_SIZE_NORMAL,
+        Mm(8.0),             // ตำแหน่ง X ทางขวา
+        y_offset - Mm(20.0), // ลดระยะห่างลง 20%
+        &fonts.bold,
+    );
+    layer.use_text(
+        context.for_month,
+        FONT_SIZE_NORMAL,
+        Mm(30.5),            // ตำแหน่ง X ถัดมา
+        y_offset - Mm(20.0), // ลดระยะห่างลง 20%
+        &fonts.italic,
+    );
+
+    // ชื่อ-นามสกุล
+    layer.use_text(
+        "ชื่อ-นามสกุล",
+        FONT_SIZE_NORMAL,
+        Mm(60.0),            // ตำแหน่ง X ถัดมา
+        y_offset - Mm(15.0), // ลดระยะห่างลง 20%
+        &fonts.bold,
+    );
+    layer.use_text(
+        &bill.name,
+        FONT_SIZE_NORMAL,
+        Mm(90.0),            // ตำแหน่ง X ถัดมา
+        y_offset - Mm(15.0), // ลดระยะห่างลง 20%
+        &fonts.italic,
+    );
+
+    // หัวตาราง
+    let table_header_y = y_offset - Mm(35.0); // ปรับระยะห่าง
+    layer.use_text(
+        "เลขมิเตอร์",
+        FONT_SIZE_NORMAL,
+        Mm(8.0), // เริ่มต้นที่ตำแหน่งเดียวกับ "วันที่ออกบิล"
+        table_header_y,
+        &fonts.bold,
+    );
+    layer.use_text(
+        "เลขอ่านครั้งหลัง",
+        FONT_SIZE_NORMAL,
+        Mm(28.0), // ปรับให้มีระยะห่างเพียงพอ
+        table_header_y,
+        &fonts.bold,
+    );
+    layer.use_text(
+        "เลขอ่านครั้งก่อน",
+        FONT_SIZE_NORMAL,
+        Mm(58.0), // ปรับให้มีระยะห่างเพียงพอ
+        table_header_y,
+        &fonts.bold,
+    );
+    layer.use_text(
+        "จำนวนหน่วย",
+        FONT_SIZE_NORMAL,
+        Mm(88.0), // ปรับให้มีระยะห่างเพียงพอ
+        table_header_y,
+        &fonts.bold,
+    );
+    layer.use_text(
+        "จำนวนเงิน",
+        FONT_SIZE_NORMAL,
+        Mm(113.0), // ปรับให้อยู่ในขอบเขต 135
+        table_header_y,
+        &fonts.bold,
+    );
+
+    // ข้อมูลในตาราง
+    let table_data_y_start = y_offset - Mm(43.0); // ปรับระยะห่าง
+    let table_data_y_line_height = Mm(6.5); // ปรับระยะห่าง
+
+    // แถวข้อมูลมิเตอร์
+    layer.use_text(
+        &bill.meter_number,
+        FONT_SIZE_NORMAL,
+        Mm(8.0), // ตรงกับหัวตาราง "หมายเลขมิเตอร์"
+        table_data_y_start,
+        &fonts.regular,
+    );
+    layer.use_text(
+        bill.current_reading.to_string(),
+        FONT_SIZE_NORMAL,
+        Mm(33.0), // ตรงกับหัวตาราง "เลขอ่านครั้งหลัง"
+        table_data_y_start,
+        &fonts.regular,
+    );
+    layer.use_text(
+        bill.previous().to_string(),
+        FONT_SIZE_NORMAL,
+        Mm(58.0), // ตรงกับหัวตาราง "เลขอ่านครั้งก่อน"
+        table_data_y_start,
+        &fonts.regular,
+    );
+
+    // แถวค่าบำรุง
+    layer.use_text(
+        "ค่าบำรุง",
+        FONT_SIZE_NORMAL,
+        Mm(89.0), // ตรงกับหัวตาราง "จำนวนหน่วย"
+        table_data_y_start,
+        &fonts.bold,
+    );
+    layer.use_text(
+        bill.maintenance_fee.to_string(),
+        FONT_SIZE_NORMAL,
+        Mm(115.0), // ตรงกับหัวตาราง "จำนวนเงิน"
+        table_data_y_start,
+        &fonts.regular,
+    );
+
+    // แถวจำนวนหน่วยและเงิน
+    layer.use_text(
+        bill.units.to_string(),
+        FONT_SIZE_NORMAL,
+        Mm(89.0), // ตรงกับหัวตาราง "จำนวนหน่วย"
+        table_data_y_start - table_data_y_line_height,
+        &fonts.regular,
+    );
+    // คำนวณค่าหน่วย (หน่วย * หน่วยละ)
+    layer.use_text(
+        bill.units_cost().to_string(),
+        FONT_SIZE_NORMAL,
+        Mm(115.0), // ตรงกับหัวตาราง "จำนวนเงิน"
+        table_data_y_start - table_data_y_line_height,
+        &fonts.regular,
+    );
+
+    // แถวรวม
+    layer.use_text(
+        "รวม",
+        FONT_SIZE_NORMAL,
+        Mm(89.0), // ตรงกับหัวตาราง "จำนวนหน่วย"
+        table_data_y_start - table_data_y_line_height * 2.0,
+        &fonts.bold,
+    );
+    layer.use_text(
+        bill.total_amount.to_string(),
+        FONT_SIZE_NORMAL,
+        Mm(115.0), // ตรงกับหัวตาราง "จำนวนเงิน"
+        table_data_y_start - table_data_y_line_height * 2.0,
+        &fonts.regular,
+    );
+
+    // สามารถเพิ่มการวาดเส้นเพื่อทำตารางได้ที่นี่
+    //เส้นใต้ข้อมูล
+    let split_line = draw_bill_split_line();
+    let id_underline = draw_line(y_offset - Mm(16.0), Mm(30.0), Mm(36.0));
+    let month_underline = draw_line(y_offset - Mm(21.0), Mm(30.0), Mm(50.0));
+    let bill_date_underline = draw_line(y_offset - Mm(27.0), Mm(30.0), Mm(55.0));
+    let name_underline = draw_line(y_offset - Mm(16.0), Mm(84.0), Mm(135.0));
+    let bill_number_underline = draw_line(y_offset - Mm(21.0), Mm(84.0), Mm(115.0));
+
+    layer.add_line(id_underline);
+    layer.add_line(month_underline);
+    layer.add_line(bill_date_underline);
+    layer.add_line(name_underline);
+    layer.add_line(bill_number_underline);
+    layer.add_line(split_line);
+
+    //เส้นตาราง
+    let header_line_top = draw_line(y_offset - Mm(30.0), Mm(6.0), Mm(135.0)); // เส้นบนตาราง จาก 8 ถึง 135
+    let header_line_bottom = draw_line(y_offset - Mm(37.0), Mm(6.0), Mm(135.0)); // เส้นล่างหัวตาราง
+                                                                                 // เมื่อพิมพ์กราฟ เส้นล่างตารางเริ่มที่เส้นแนวตั้ง ด้านซ้ายเป็นพื้นที่ของกราฟ
+    let middle_line_start = if context.options.usage_chart.is_some() {
+        Mm(87.5)
+    } else {
+        Mm(6.0)
+    };
+    let middle_line = draw_line(y_offset - Mm(51.0), middle_line_start, Mm(135.0)); // เส้นล่างตาราง
+    let vertical_line = draw_vetical_line(Mm(87.5), y_offset - Mm(29.8), y_offset - Mm(58.0)); // เส้นแนวตั้งระหว่าง "เลขอ่านครั้งก่อน" และ "จำนวนหน่วย"
+
+    layer.add_line(header_line_top);
+    layer.add_line(header_line_bottom);
+    layer.add_line(middle_line);
+    layer.add_line(vertical_line);
+
+    // คำเตือนสำหรับบิลที่ใช้น้ำสูงผิดปกติ ถัดจากวันออกบิล
+    if context
+        .options
+        .leak_notices
+        .contains(bill.meter_number.trim())
+    {
+        layer.use_text(
+            "โปรดตรวจสอบรอยรั่ว",
+            FONT_SIZE_NORMAL,
+            Mm(60.0),
+            y_offset - Mm(26.0),
+            &fonts.bold,
+        );
+    }
+
+    if let Some(usage) = &context.options.usage_chart {
+        // ข้างตารางค่าน้ำ ใต้แถวเลขอ่านมิเตอร์ ด้านซ้ายของเส้นแนวตั้ง
+        usage.draw(
+            layer,
+            (&fonts.regular, &fonts.bold),
+            &bill.meter_number,
+            8.0,
+            (y_offset - Mm(58.0)).0,
+            78.0,
+            13.0,
+        );
+    }
+
+    if context.options.payment_stub {
+        draw_payment_stub(layer, fonts, slip, y_offset, context);
+    }
+}
+
+// ส่วนต่อท้ายใบเสร็จสำหรับผู้เก็บเงินฉีกเก็บไว้ คั่นด้วยเส้นประ
+fn draw_payment_stub(
+    layer: &PdfLayerReference,
+    fonts: &BillFonts,
+    slip: &BillSlip,
+    y_offset: Mm,
+    context: &BillContext,
+) {
+    let bill = slip.bill;
+    let cut_y = y_offset - Mm(60.0);
+    log::log_debug(&format!("เขียนส่วนต่อท้ายสำหรับผู้เก็บเงิน: {}", slip.bill_number));
+
+    // เส้นประสำหรับฉีก
+    for segment in draw_dashed_line(cut_y, Mm(4.0), Mm(144.0), Mm(2.0), Mm(1.5)) {
+        layer.add_line(segment);
+    }
+    layer.use_text(
+        "ฉีกตามรอยประ",
+        FONT_SIZE_NORMAL - 3.0,
+        Mm(120.0),
+        cut_y + Mm(1.0),
+        &fonts.regular,
+    );
+
+    let first_row_y = cut_y - Mm(5.0);
+    let line_height = Mm(5.0);
+    let rows = [
+        (
+            ("ส่วนของผู้เก็บเงิน", String::new()),
+            ("เลขที่บิล", slip.bill_number.clone()),
+        ),
+        (
+            ("ชื่อ-นามสกุล", bill.name.clone()),
+            ("เลขมิเตอร์", bill.meter_number.clone()),
+        ),
+        (
+            ("จำนวนเงิน", format!("{} บาท", bill.total_amount)),
+            ("ชำระภายใน", context.date_text(context.due_date)),
+        ),
+    ];
+    for (k, ((left_label, left_value), (right_label, right_value))) in rows.iter().enumerate() {
+        let y = first_row_y - line_height * k as f32;
+        layer.use_text(*left_label, FONT_SIZE_NORMAL, Mm(8.0), y, &fonts.bold);
+        layer.use_text(left_value, FONT_SIZE_NORMAL, Mm(30.0), y, &fonts.italic);
+        layer.use_text(*right_label, FONT_SIZE_NORMAL, Mm(80.0), y, &fonts.bold);
+        layer.use_text(right_value, FONT_SIZE_NORMAL, Mm(100.0), y, &fonts.italic);
+    }
+    layer.use_text(
+        "ผู้รับเงิน ..............................",
+        FONT_SIZE_NORMAL,
+        Mm(80.0),
+        first_row_y - line_height * 3.0,
+        &fonts.regular,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bill(order: u32, meter_number: &str) -> BillRecord {
+        BillRecord {
+            order,
+            meter_number: meter_number.to_string(),
+            name: "นายสมชาย".to_string(),
+            previous_reading: Some(1200),
+            current_reading: 1250,
+            units: 50,
+            maintenance_fee: 20,
+            rate_per_unit: 2,
+            total_amount: 120,
+            zone: String::new(),
         }
     }
 
-    // บันทึกไฟล์ PDF
-    log::log_info(&format!("กำลังบันทึกไฟล์ PDF: {}", output_path));
-    doc.save(&mut BufWriter::new(File::create(output_path)?))?;
-    log::log_info("บันทึกไฟล์ PDF สำเร็จ!");
-    Ok(())
+    #[test]
+    fn per_customer_path_fills_placeholders() {
+        assert_eq!(
+            per_customer_path(
+                "bills/{month}/{order}-{meter}.pdf",
+                "ต.ค. 68",
+                &bill(7, "M/07")
+            ),
+            "bills/ต.ค. 68/007-M_07.pdf"
+        );
+    }
+
+    #[test]
+    fn duplicate_per_customer_paths_are_rejected_before_writing() {
+        let dir =
+            std::env::temp_dir().join(format!("papa_moo_3_bills_test_{}", std::process::id()));
+        let pattern = format!("{}/{{name}}.pdf", dir.display());
+        let result = create_per_customer_pdfs(
+            &[bill(1, "M001"), bill(2, "M002")],
+            &pattern,
+            "ต.ค. 68",
+            &BillOptions::default(),
+        );
+        assert!(
+            matches!(result, Err(Error::Validation(message)) if message.contains("บิลลำดับ 1 และ 2"))
+        );
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn separate_copies_return_the_files_they_write() {
+        let dir =
+            std::env::temp_dir().join(format!("papa_moo_3_bills_separate_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("plumbing.pdf").to_string_lossy().into_owned();
+        let options = BillOptions {
+            copy_mode: CopyMode::Separate,
+            ..BillOptions::default()
+        };
+
+        let single = create_pdf(&[bill(1, "M001")], &output, "ต.ค. 68", &options).unwrap();
+        let per_customer = create_per_customer_pdfs(
+            &[bill(1, "M001"), bill(2, "M002")],
+            &format!("{}/{{order}}.pdf", dir.display()),
+            "ต.ค. 68",
+            &options,
+        )
+        .unwrap();
+        let exists = |paths: &[String]| paths.iter().all(|path| Path::new(path).exists());
+        let names = |paths: &[String]| -> Vec<String> {
+            paths
+                .iter()
+                .map(|path| {
+                    Path::new(path)
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect()
+        };
+        let all_written = exists(&single) && exists(&per_customer);
+        let output_written = Path::new(&output).exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            names(&single),
+            ["plumbing_customer.pdf", "plumbing_office.pdf"]
+        );
+        assert_eq!(
+            names(&per_customer),
+            [
+                "001_customer.pdf",
+                "001_office.pdf",
+                "002_customer.pdf",
+                "002_office.pdf"
+            ]
+        );
+        assert!(all_written);
+        assert!(!output_written);
+    }
+
+    #[test]
+    fn same_bills_give_identical_bytes() {
+        let options = || BillOptions {
+            copy_mode: CopyMode::SameSheet,
+            payment_stub: true,
+            issue_date: NaiveDate::from_ymd_opt(2025, 10, 5).unwrap(),
+            ..BillOptions::default()
+        };
+        let bills = [bill(1, "M001"), bill(2, "M002"), bill(3, "M003")];
+        let first = create_pdf_bytes(&bills, "ต.ค. 68", &options()).unwrap();
+        let second = create_pdf_bytes(&bills, "ต.ค. 68", &options()).unwrap();
+        assert!(first == second, "PDF สองครั้งไม่เหมือนกัน");
+
+        // วันออกบิลต่างกัน ได้ไฟล์ต่างกัน (ไม่ได้เหมือนกันเพราะไม่ได้ใส่วันที่)
+        let next_day = BillOptions {
+            issue_date: NaiveDate::from_ymd_opt(2025, 10, 6).unwrap(),
+            ..options()
+        };
+        assert!(first != create_pdf_bytes(&bills, "ต.ค. 68", &next_day).unwrap());
+    }
 }
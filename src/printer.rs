@@ -0,0 +1,509 @@
+use crate::error::Error;
+use crate::log;
+use crate::Result;
+use reqwest::blocking::Client;
+use reqwest::header::CONTENT_TYPE;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+// ส่งไฟล์ใหญ่ไปเครื่องพิมพ์ผ่านเครือข่ายอาจใช้เวลานาน
+const IPP_TIMEOUT: Duration = Duration::from_secs(120);
+const IPP_DEFAULT_PORT: u16 = 631;
+const USER_NAME: &str = "papa_moo_3_bills";
+
+// รหัสคำสั่งและชนิดข้อมูลของโปรโตคอล IPP (RFC 8010/8011)
+const IPP_VERSION: [u8; 2] = [1, 1];
+const OPERATION_PRINT_JOB: u16 = 0x0002;
+const TAG_OPERATION: u8 = 0x01;
+const TAG_JOB: u8 = 0x02;
+const TAG_END: u8 = 0x03;
+const VALUE_INTEGER: u8 = 0x21;
+const VALUE_BEGIN_COLLECTION: u8 = 0x34;
+const VALUE_END_COLLECTION: u8 = 0x37;
+const VALUE_NAME: u8 = 0x42;
+const VALUE_KEYWORD: u8 = 0x44;
+const VALUE_URI: u8 = 0x45;
+const VALUE_CHARSET: u8 = 0x47;
+const VALUE_LANGUAGE: u8 = 0x48;
+const VALUE_MIME_TYPE: u8 = 0x49;
+const VALUE_MEMBER_NAME: u8 = 0x4A;
+
+/// การพิมพ์สองหน้า
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Duplex {
+    /// พลิกตามขอบยาว (กระดาษแนวตั้ง)
+    LongEdge,
+    /// พลิกตามขอบสั้น (กระดาษแนวนอน)
+    ShortEdge,
+}
+
+// ค่า sides ตามมาตรฐาน IPP ใช้ได้ทั้ง CUPS และ IPP
+fn sides(duplex: Option<Duplex>) -> &'static str {
+    match duplex {
+        None => "one-sided",
+        Some(Duplex::LongEdge) => "two-sided-long-edge",
+        Some(Duplex::ShortEdge) => "two-sided-short-edge",
+    }
+}
+
+/// เครื่องพิมพ์ปลายทาง
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrintTarget {
+    /// ชื่อเครื่องพิมพ์ใน CUPS ของเครื่องนี้ (ส่งด้วยคำสั่ง `lp`)
+    Cups(String),
+    /// ที่อยู่ IPP ของเครื่องพิมพ์ เช่น ipp://192.168.1.20/ipp/print
+    Ipp(String),
+}
+
+impl PrintTarget {
+    /// ที่อยู่ที่ขึ้นต้นด้วย ipp://, ipps://, http:// หรือ https:// เป็น IPP นอกนั้นเป็นชื่อเครื่องพิมพ์ใน CUPS
+    pub fn parse(text: &str) -> Self {
+        let is_url = ["ipp://", "ipps://", "http://", "https://"]
+            .iter()
+            .any(|scheme| text.starts_with(scheme));
+        if is_url {
+            PrintTarget::Ipp(text.to_string())
+        } else {
+            PrintTarget::Cups(text.to_string())
+        }
+    }
+}
+
+/// ตัวเลือกการพิมพ์
+#[derive(Debug, Clone)]
+pub struct PrintOptions {
+    pub copies: u32,
+    /// ไม่ระบุคือพิมพ์หน้าเดียว
+    pub duplex: Option<Duplex>,
+    /// ถาดกระดาษ ชื่อตามที่เครื่องพิมพ์รองรับ เช่น tray-2
+    pub tray: Option<String>,
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        PrintOptions {
+            copies: 1,
+            duplex: None,
+            tray: None,
+        }
+    }
+}
+
+/// ส่งไฟล์ PDF ไปพิมพ์ที่เครื่องพิมพ์ปลายทาง
+pub fn print_file(path: &str, target: &PrintTarget, options: &PrintOptions) -> Result<()> {
+    log::log_info(&format!(
+        "กำลังส่ง {} ไปพิมพ์ {} ชุด ({})",
+        path,
+        options.copies,
+        sides(options.duplex)
+    ));
+    match target {
+        PrintTarget::Cups(printer) => print_cups(path, printer, options),
+        PrintTarget::Ipp(url) => print_ipp(path, url, options),
+    }
+}
+
+// ใช้คำสั่ง lp ของ CUPS ถาดกระดาษส่งเป็น InputSlot (ดูชื่อถาดได้ด้วย lpoptions -p <ชื่อ> -l)
+fn print_cups(path: &str, printer: &str, options: &PrintOptions) -> Result<()> {
+    let mut command = Command::new("lp");
+    command
+        .args(["-d", printer])
+        .args(["-n", &options.copies.to_string()])
+        .args(["-o", &format!("sides={}", sides(options.duplex))]);
+    if let Some(tray) = &options.tray {
+        command.args(["-o", &format!("InputSlot={}", tray)]);
+    }
+    let output = command.arg("--").arg(path).output().map_err(|error| {
+        Error::Print(format!("เรียกคำสั่ง lp ไม่ได้ (ติดตั้ง CUPS แล้วหรือยัง): {}", error))
+    })?;
+    if !output.status.success() {
+        return Err(Error::Print(format!(
+            "lp ส่งงานพิมพ์ไปยัง {} ไม่สำเร็จ: {}",
+            printer,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    // lp แจ้งหมายเลขงานพิมพ์ เช่น "request id is office-42 (1 file(s))"
+    log::log_info(String::from_utf8_lossy(&output.stdout).trim());
+    Ok(())
+}
+
+// ส่งคำสั่ง Print-Job ของ IPP ทาง HTTP ไปยังเครื่องพิมพ์โดยตรง ไม่ต้องมี CUPS
+fn print_ipp(path: &str, url: &str, options: &PrintOptions) -> Result<()> {
+    let document = fs::read(path)?;
+    let job_name = Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string());
+    let mut request = print_job_request(url, &job_name, options);
+    request.extend_from_slice(&document);
+
+    let response = Client::builder()
+        .timeout(IPP_TIMEOUT)
+        .build()?
+        .post(http_url(url))
+        .header(CONTENT_TYPE, "application/ipp")
+        .body(request)
+        .send()
+        .map_err(|error| Error::Print(format!("ติดต่อเครื่องพิมพ์ {} ไม่ได้: {}", url, error)))?;
+    if !response.status().is_success() {
+        return Err(Error::Print(format!(
+            "เครื่องพิมพ์ {} ตอบ HTTP {}",
+            url,
+            response.status()
+        )));
+    }
+    let body = response
+        .bytes()
+        .map_err(|error| Error::Print(format!("อ่านคำตอบจากเครื่องพิมพ์ {} ไม่ได้: {}", url, error)))?;
+    let (status, job_id) = parse_response(&body)
+        .ok_or_else(|| Error::Print(format!("อ่านคำตอบจากเครื่องพิมพ์ {} ไม่ได้", url)))?;
+    // รหัส 0x0000-0x00FF คือสำเร็จ
+    if status > 0x00FF {
+        return Err(Error::Print(format!(
+            "เครื่องพิมพ์ {} ไม่รับงานพิมพ์ (IPP status 0x{:04X})",
+            url, status
+        )));
+    }
+    match job_id {
+        Some(job_id) => log::log_info(&format!("ส่งงานพิมพ์แล้ว หมายเลขงาน {}", job_id)),
+        None => log::log_info("ส่งงานพิมพ์แล้ว"),
+    }
+    Ok(())
+}
+
+// ipp://host/path -> http://host:631/path, ipps:// -> https://
+fn http_url(url: &str) -> String {
+    let (scheme, rest) = match url.split_once("://") {
+        Some(("ipp", rest)) => ("http", rest),
+        Some(("ipps", rest)) => ("https", rest),
+        _ => return url.to_string(),
+    };
+    let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    // ที่อยู่ IPv6 อยู่ในวงเล็บ เช่น [fe80::1]:631 พอร์ตจึงอยู่หลังวงเล็บปิด
+    let after_address = host.rsplit_once(']').map_or(host, |(_, rest)| rest);
+    if after_address.contains(':') {
+        format!("{}://{}{}", scheme, host, path)
+    } else {
+        format!("{}://{}:{}{}", scheme, host, IPP_DEFAULT_PORT, path)
+    }
+}
+
+// ข้อมูลหนึ่งค่าในคำขอ IPP: ชนิด ความยาวชื่อ ชื่อ ความยาวค่า ค่า
+fn push_attribute(buffer: &mut Vec<u8>, value_tag: u8, name: &str, value: &[u8]) {
+    buffer.push(value_tag);
+    buffer.extend_from_slice(&(name.len() as u16).to_be_bytes());
+    buffer.extend_from_slice(name.as_bytes());
+    buffer.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    buffer.extend_from_slice(value);
+}
+
+fn print_job_request(url: &str, job_name: &str, options: &PrintOptions) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    buffer.extend_from_slice(&IPP_VERSION);
+    buffer.extend_from_slice(&OPERATION_PRINT_JOB.to_be_bytes());
+    // request-id
+    buffer.extend_from_slice(&1u32.to_be_bytes());
+
+    buffer.push(TAG_OPERATION);
+    push_attribute(&mut buffer, VALUE_CHARSET, "attributes-charset", b"utf-8");
+    push_attribute(
+        &mut buffer,
+        VALUE_LANGUAGE,
+        "attributes-natural-language",
+        b"th",
+    );
+    push_attribute(&mut buffer, VALUE_URI, "printer-uri", url.as_bytes());
+    push_attribute(
+        &mut buffer,
+        VALUE_NAME,
+        "requesting-user-name",
+        USER_NAME.as_bytes(),
+    );
+    push_attribute(&mut buffer, VALUE_NAME, "job-name", job_name.as_bytes());
+    push_attribute(
+        &mut buffer,
+        VALUE_MIME_TYPE,
+        "document-format",
+        b"application/pdf",
+    );
+
+    buffer.push(TAG_JOB);
+    push_attribute(
+        &mut buffer,
+        VALUE_INTEGER,
+        "copies",
+        &options.copies.to_be_bytes(),
+    );
+    push_attribute(
+        &mut buffer,
+        VALUE_KEYWORD,
+        "sides",
+        sides(options.duplex).as_bytes(),
+    );
+    // ถาดกระดาษอยู่ใน media-col { media-source }
+    if let Some(tray) = &options.tray {
+        push_attribute(&mut buffer, VALUE_BEGIN_COLLECTION, "media-col", b"");
+        push_attribute(&mut buffer, VALUE_MEMBER_NAME, "", b"media-source");
+        push_attribute(&mut buffer, VALUE_KEYWORD, "", tray.as_bytes());
+        push_attribute(&mut buffer, VALUE_END_COLLECTION, "", b"");
+    }
+    buffer.push(TAG_END);
+    buffer
+}
+
+// อ่านรหัสสถานะและหมายเลขงานพิมพ์ (job-id) จากคำตอบ IPP
+fn parse_response(body: &[u8]) -> Option<(u16, Option<i32>)> {
+    let status = u16::from_be_bytes([*body.get(2)?, *body.get(3)?]);
+    let mut position = 8;
+    let mut job_id = None;
+    while let Some(&tag) = body.get(position) {
+        position += 1;
+        // ตัวคั่นกลุ่ม (ค่าน้อยกว่า 0x10) ไม่มีชื่อและค่าตามหลัง
+        if tag == TAG_END {
+            break;
+        }
+        if tag < 0x10 {
+            continue;
+        }
+        let name_length = usize::from(u16::from_be_bytes([
+            *body.get(position)?,
+            *body.get(position + 1)?,
+        ]));
+        let name = body.get(position + 2..position + 2 + name_length)?;
+        position += 2 + name_length;
+        let value_length = usize::from(u16::from_be_bytes([
+            *body.get(position)?,
+            *body.get(position + 1)?,
+        ]));
+        let value = body.get(position + 2..position + 2 + value_length)?;
+        position += 2 + value_length;
+        if tag == VALUE_INTEGER && name == b"job-id" && value.len() == 4 {
+            job_id = Some(i32::from_be_bytes([value[0], value[1], value[2], value[3]]));
+        }
+    }
+    Some((status, job_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    // แยกคำขอ IPP เป็นรายการ (ชนิด, ชื่อ, ค่า) ตัวคั่นกลุ่มได้ชื่อและค่าว่าง
+    fn attributes(body: &[u8]) -> Vec<(u8, String, Vec<u8>)> {
+        let mut items = Vec::new();
+        let mut position = 8;
+        while position < body.len() {
+            let tag = body[position];
+            position += 1;
+            if tag < 0x10 {
+                items.push((tag, String::new(), Vec::new()));
+                if tag == TAG_END {
+                    break;
+                }
+                continue;
+            }
+            let length = |at: usize| usize::from(u16::from_be_bytes([body[at], body[at + 1]]));
+            let name_length = length(position);
+            let name = String::from_utf8(body[position + 2..position + 2 + name_length].to_vec());
+            position += 2 + name_length;
+            let value_length = length(position);
+            let value = body[position + 2..position + 2 + value_length].to_vec();
+            position += 2 + value_length;
+            items.push((tag, name.unwrap(), value));
+        }
+        items
+    }
+
+    // คำตอบ IPP ที่มีรหัสสถานะและ job-id
+    fn response(status: u16, job_id: i32) -> Vec<u8> {
+        let mut body = vec![1, 1];
+        body.extend_from_slice(&status.to_be_bytes());
+        body.extend_from_slice(&1u32.to_be_bytes());
+        body.push(TAG_OPERATION);
+        push_attribute(&mut body, VALUE_CHARSET, "attributes-charset", b"utf-8");
+        body.push(TAG_JOB);
+        push_attribute(&mut body, VALUE_INTEGER, "job-id", &job_id.to_be_bytes());
+        push_attribute(&mut body, VALUE_KEYWORD, "job-state-reasons", b"none");
+        body.push(TAG_END);
+        body
+    }
+
+    // เครื่องพิมพ์จำลองรับคำขอหนึ่งครั้ง ตอบด้วย `reply` แล้วคืนเส้นทางและเนื้อหาคำขอ
+    fn ipp_server(reply: Vec<u8>) -> (u16, thread::JoinHandle<(String, Vec<u8>)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim().to_ascii_lowercase();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some(value) = line.strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let stream = reader.get_mut();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/ipp\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                reply.len()
+            )
+            .unwrap();
+            stream.write_all(&reply).unwrap();
+            let path = request_line.split_whitespace().nth(1).unwrap().to_string();
+            (path, body)
+        });
+        (port, handle)
+    }
+
+    fn document() -> String {
+        let path =
+            std::env::temp_dir().join(format!("papa_moo_3_print_{}.pdf", std::process::id()));
+        fs::write(&path, b"%PDF-1.4 test").unwrap();
+        path.display().to_string()
+    }
+
+    #[test]
+    fn print_job_request_has_operation_and_job_attributes() {
+        let options = PrintOptions {
+            copies: 2,
+            duplex: Some(Duplex::LongEdge),
+            tray: Some("tray-2".to_string()),
+        };
+        let request = print_job_request("ipp://printer/ipp/print", "bills.pdf", &options);
+        assert_eq!(&request[..8], &[1, 1, 0, 2, 0, 0, 0, 1]);
+
+        let items = attributes(&request);
+        let expected: Vec<(u8, &str, &[u8])> = vec![
+            (TAG_OPERATION, "", b""),
+            (VALUE_CHARSET, "attributes-charset", b"utf-8"),
+            (VALUE_LANGUAGE, "attributes-natural-language", b"th"),
+            (VALUE_URI, "printer-uri", b"ipp://printer/ipp/print"),
+            (VALUE_NAME, "requesting-user-name", USER_NAME.as_bytes()),
+            (VALUE_NAME, "job-name", b"bills.pdf"),
+            (VALUE_MIME_TYPE, "document-format", b"application/pdf"),
+            (TAG_JOB, "", b""),
+            (VALUE_INTEGER, "copies", &[0, 0, 0, 2]),
+            (VALUE_KEYWORD, "sides", b"two-sided-long-edge"),
+            (VALUE_BEGIN_COLLECTION, "media-col", b""),
+            (VALUE_MEMBER_NAME, "", b"media-source"),
+            (VALUE_KEYWORD, "", b"tray-2"),
+            (VALUE_END_COLLECTION, "", b""),
+            (TAG_END, "", b""),
+        ];
+        let items: Vec<(u8, &str, &[u8])> = items
+            .iter()
+            .map(|(tag, name, value)| (*tag, name.as_str(), value.as_slice()))
+            .collect();
+        assert_eq!(items, expected);
+    }
+
+    #[test]
+    fn print_job_request_without_tray_has_no_media_col() {
+        let request = print_job_request(
+            "ipp://printer/ipp/print",
+            "bills.pdf",
+            &PrintOptions::default(),
+        );
+        let items = attributes(&request);
+        assert!(items.iter().all(|(_, name, _)| name != "media-col"));
+        assert!(items
+            .iter()
+            .any(|(_, name, value)| name == "sides" && value == b"one-sided"));
+    }
+
+    #[test]
+    fn parse_response_reads_status_and_job_id() {
+        assert_eq!(
+            parse_response(&response(0x0000, 77)),
+            Some((0x0000, Some(77)))
+        );
+        assert_eq!(
+            parse_response(&response(0x0400, 0)),
+            Some((0x0400, Some(0)))
+        );
+        // คำตอบที่ไม่มีกลุ่มข้อมูลงานพิมพ์
+        assert_eq!(
+            parse_response(&[1, 1, 0, 1, 0, 0, 0, 1, TAG_END]),
+            Some((0x0001, None))
+        );
+        // คำตอบที่ถูกตัดกลางทาง
+        assert_eq!(parse_response(&response(0, 77)[..20]), None);
+        assert_eq!(parse_response(&[1, 1]), None);
+    }
+
+    #[test]
+    fn print_ipp_sends_job_to_printer() {
+        let (port, server) = ipp_server(response(0x0000, 77));
+        let url = format!("ipp://127.0.0.1:{}/ipp/print", port);
+        print_file(
+            &document(),
+            &PrintTarget::parse(&url),
+            &PrintOptions::default(),
+        )
+        .unwrap();
+
+        let (path, body) = server.join().unwrap();
+        assert_eq!(path, "/ipp/print");
+        assert!(body.ends_with(b"%PDF-1.4 test"));
+        assert!(attributes(&body)
+            .iter()
+            .any(|(_, name, value)| name == "printer-uri" && value == url.as_bytes()));
+    }
+
+    #[test]
+    fn print_ipp_reports_rejected_job() {
+        let (port, server) = ipp_server(response(0x0400, 0));
+        let url = format!("ipp://127.0.0.1:{}/ipp/print", port);
+        let result = print_file(
+            &document(),
+            &PrintTarget::parse(&url),
+            &PrintOptions::default(),
+        );
+        assert!(matches!(result, Err(Error::Print(message)) if message.contains("0x0400")));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn ipv6_address_without_port_gets_default_port() {
+        assert_eq!(
+            http_url("ipp://[fe80::1]/ipp/print"),
+            "http://[fe80::1]:631/ipp/print"
+        );
+        assert_eq!(
+            http_url("ipps://[fe80::1]:8631/ipp/print"),
+            "https://[fe80::1]:8631/ipp/print"
+        );
+    }
+
+    #[test]
+    fn ipv4_and_host_names() {
+        assert_eq!(
+            http_url("ipp://192.168.1.20/ipp/print"),
+            "http://192.168.1.20:631/ipp/print"
+        );
+        assert_eq!(
+            http_url("ipp://printer.local:8000/ipp"),
+            "http://printer.local:8000/ipp"
+        );
+        assert_eq!(
+            http_url("http://printer.local/ipp"),
+            "http://printer.local/ipp"
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use crate::error::Error;
+use crate::Result;
+use qrcode::{Color, EcLevel, QrCode};
+
+// รหัสแอปพลิเคชันพร้อมเพย์ในมาตรฐาน EMVCo (Thai QR Payment)
+const PROMPTPAY_AID: &str = "A000000677010111";
+// รหัสสกุลเงินบาท (ISO 4217) และรหัสประเทศ
+const CURRENCY_THB: &str = "764";
+const COUNTRY_TH: &str = "TH";
+
+// ขนาดภาพ QR: จุดละกี่พิกเซล และขอบขาวรอบภาพกี่จุด
+const MODULE_PIXELS: usize = 8;
+const QUIET_ZONE: usize = 4;
+
+/// บัญชีพร้อมเพย์ที่รับเงินค่าน้ำ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PromptPayId {
+    /// เบอร์โทรศัพท์มือถือ 10 หลัก
+    Phone(String),
+    /// เลขประจำตัวประชาชนหรือเลขประจำตัวผู้เสียภาษี 13 หลัก
+    TaxId(String),
+}
+
+impl std::str::FromStr for PromptPayId {
+    type Err = String;
+
+    /// รับเบอร์โทรศัพท์ 10 หลัก หรือเลขประจำตัว 13 หลัก (มีขีดหรือช่องว่างคั่นได้)
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        let digits: String = text.chars().filter(|c| !matches!(c, '-' | ' ')).collect();
+        if !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("พร้อมเพย์ \"{}\" ต้องเป็นตัวเลขเท่านั้น", text));
+        }
+        match digits.len() {
+            10 if digits.starts_with('0') => Ok(PromptPayId::Phone(digits)),
+            13 => Ok(PromptPayId::TaxId(digits)),
+            _ => Err(format!(
+                "พร้อมเพย์ \"{}\" ต้องเป็นเบอร์มือถือ 10 หลัก หรือเลขประจำตัว 13 หลัก",
+                text
+            )),
+        }
+    }
+}
+
+// ข้อมูลหนึ่งช่องในรูปแบบ EMVCo: รหัส 2 หลัก ความยาว 2 หลัก แล้วตามด้วยค่า
+fn field(id: &str, value: &str) -> String {
+    format!("{}{:02}{}", id, value.len(), value)
+}
+
+// CRC-16/CCITT-FALSE ตามที่มาตรฐาน Thai QR Payment กำหนด
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0xFFFF, |crc, byte| {
+        (0..8).fold(crc ^ (u16::from(*byte) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+/// ข้อความใน QR พร้อมเพย์ที่ระบุยอดเงิน (บาท) แอปธนาคารสแกนแล้วจะได้ผู้รับและยอดเงินทันที
+/// ยอดเงิน 0 บาทจะได้ QR ที่ผู้จ่ายกรอกยอดเอง
+pub fn payload(id: &PromptPayId, amount: u32) -> String {
+    let account = match id {
+        // เบอร์โทรศัพท์ใช้รหัสประเทศ 66 แทนเลข 0 ตัวแรก เติมศูนย์หน้าให้ครบ 13 หลัก
+        PromptPayId::Phone(phone) => field("01", &format!("0066{}", &phone[1..])),
+        PromptPayId::TaxId(tax_id) => field("02", tax_id),
+    };
+    // 11 = QR ใช้ซ้ำได้ (ไม่มียอดเงิน) 12 = QR ใช้ครั้งเดียว (มียอดเงิน)
+    let method = if amount == 0 { "11" } else { "12" };
+    let mut text = [
+        field("00", "01"),
+        field("01", method),
+        field("29", &format!("{}{}", field("00", PROMPTPAY_AID), account)),
+        field("58", COUNTRY_TH),
+        field("53", CURRENCY_THB),
+    ]
+    .concat();
+    if amount > 0 {
+        text.push_str(&field("54", &format!("{}.00", amount)));
+    }
+    text.push_str("6304");
+    let checksum = crc16(text.as_bytes());
+    format!("{}{:04X}", text, checksum)
+}
+
+/// ภาพ QR (PNG ขาวดำ) ของข้อความ
+pub fn qr_png(text: &str) -> Result<Vec<u8>> {
+    let code = QrCode::with_error_correction_level(text, EcLevel::M)
+        .map_err(|error| Error::Validation(format!("สร้าง QR ไม่ได้: {}", error)))?;
+    let modules = code.width();
+    let colors = code.to_colors();
+    let size = (modules + QUIET_ZONE * 2) * MODULE_PIXELS;
+
+    let mut pixels = vec![u8::MAX; size * size];
+    for (index, color) in colors.iter().enumerate() {
+        if *color != Color::Dark {
+            continue;
+        }
+        let left = (index % modules + QUIET_ZONE) * MODULE_PIXELS;
+        let top = (index / modules + QUIET_ZONE) * MODULE_PIXELS;
+        for y in top..top + MODULE_PIXELS {
+            pixels[y * size + left..y * size + left + MODULE_PIXELS].fill(0);
+        }
+    }
+
+    let png_error =
+        |error: png::EncodingError| Error::Validation(format!("สร้างภาพ QR ไม่ได้: {}", error));
+    let mut image = Vec::new();
+    let mut encoder = png::Encoder::new(&mut image, size as u32, size as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(png_error)?;
+    writer.write_image_data(&pixels).map_err(png_error)?;
+    writer.finish().map_err(png_error)?;
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc16_matches_ccitt_false_check_value() {
+        assert_eq!(crc16(b"123456789"), 0x29B1);
+    }
+
+    #[test]
+    fn static_payload_for_phone() {
+        let id: PromptPayId = "080-123-4567".parse().unwrap();
+        assert_eq!(
+            payload(&id, 0),
+            "00020101021129370016A000000677010111011300668012345675802TH530376463046197"
+        );
+    }
+
+    #[test]
+    fn static_payload_for_tax_id() {
+        let id: PromptPayId = "1111111111111".parse().unwrap();
+        assert_eq!(
+            payload(&id, 0),
+            "00020101021129370016A000000677010111021311111111111115802TH530376463047B5A"
+        );
+    }
+
+    #[test]
+    fn payload_with_amount() {
+        let id = PromptPayId::Phone("0812345678".to_string());
+        let text = payload(&id, 80);
+        assert!(text.starts_with("000201010212"));
+        assert!(text.contains("5802TH5303764540580.006304"));
+        let (body, checksum) = text.split_at(text.len() - 4);
+        assert!(body.ends_with("6304"));
+        assert_eq!(checksum, format!("{:04X}", crc16(body.as_bytes())));
+    }
+
+    #[test]
+    fn rejects_wrong_length() {
+        assert!("12345".parse::<PromptPayId>().is_err());
+        assert!("1234567890".parse::<PromptPayId>().is_err());
+    }
+}
@@ -0,0 +1,176 @@
+use super::drawing::{draw_line, draw_rect, draw_vetical_line};
+use super::font_util::find_thai_font;
+use super::pdf_util::save_pdf;
+use crate::log;
+use crate::model::BillRecord;
+use crate::Result;
+use chrono::NaiveDate;
+use printpdf::*;
+use std::collections::BTreeMap;
+
+// ขนาดกระดาษ A4 แนวตั้ง
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+
+const TABLE_LEFT: f32 = 10.0;
+const TABLE_RIGHT: f32 = 200.0;
+const TABLE_TOP: f32 = 262.0;
+const ROW_HEIGHT: f32 = 10.0;
+const ROWS_PER_PAGE: usize = 23;
+
+// (หัวคอลัมน์, ตำแหน่ง X เริ่มต้นของคอลัมน์)
+const COLUMNS: [(&str, f32); 6] = [
+    ("ลำดับ", 10.0),
+    ("ชื่อ-นามสกุล", 24.0),
+    ("เลขมิเตอร์", 84.0),
+    ("เลขอ่านครั้งก่อน", 109.0),
+    ("เลขอ่านครั้งนี้", 137.0),
+    ("หมายเหตุ", 170.0),
+];
+
+const NO_ZONE: &str = "ไม่ระบุโซน";
+
+/// สร้างใบจดเลขมิเตอร์สำหรับวันอ่านมิเตอร์ เรียงตามลำดับเส้นทางและแยกหน้าตามโซน
+/// `records` คือข้อมูลของเดือนก่อน เลขอ่านครั้งหลังของเดือนก่อนจะพิมพ์เป็นเลขอ่านครั้งก่อน
+pub fn create_route_sheet_pdf(
+    records: &[BillRecord],
+    output_path: &str,
+    for_month: &str,
+    issue_date: NaiveDate,
+) -> Result<()> {
+    log::log_info("เริ่มสร้าง PDF ใบจดเลขมิเตอร์...");
+
+    log::log_info("สร้างเอกสาร PDF ขนาด A4 แนวตั้ง (210mm x 297mm)");
+    let (doc, page1, layer1) =
+        PdfDocument::new("ใบจดเลขมิเตอร์", Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+
+    let (font, bold_font, _italic_font) = find_thai_font(&doc)?;
+    let font_size_normal = 12.0;
+    let font_size_subtitle = 14.0;
+    let font_size_title = 16.0;
+
+    // จัดกลุ่มตามโซน แล้วเรียงตามลำดับในแต่ละโซน
+    let mut zones: BTreeMap<&str, Vec<&BillRecord>> = BTreeMap::new();
+    for record in records {
+        let zone = match record.zone.trim() {
+            "" => NO_ZONE,
+            zone => zone,
+        };
+        zones.entry(zone).or_default().push(record);
+    }
+    for customers in zones.values_mut() {
+        customers.sort_by_key(|record| record.order);
+    }
+    log::log_info(&format!("จำนวนโซน: {}", zones.len()));
+
+    // แต่ละโซนขึ้นหน้าใหม่ เพื่อแยกแผ่นให้ผู้จดแต่ละเส้นทางได้
+    let pages: Vec<(&str, usize, usize, &[&BillRecord])> = zones
+        .iter()
+        .flat_map(|(zone, customers)| {
+            let chunks: Vec<_> = customers.chunks(ROWS_PER_PAGE).collect();
+            let zone_pages = chunks.len();
+            chunks
+                .into_iter()
+                .enumerate()
+                .map(move |(i, chunk)| (*zone, i + 1, zone_pages, chunk))
+        })
+        .collect();
+    log::log_info(&format!("จำนวนหน้าทั้งหมด: {}", pages.len()));
+
+    for (i, (zone, zone_page, zone_pages, customers)) in pages.iter().enumerate() {
+        let current_layer = if i == 0 {
+            doc.get_page(page1).get_layer(layer1)
+        } else {
+            log::log_debug("สร้างหน้าใหม่");
+            let (page, layer) =
+                doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), format!("Page {}", i + 1));
+            doc.get_page(page).get_layer(layer)
+        };
+
+        // หัวเอกสาร
+        current_layer.use_text(
+            "การประปาหมู่บ้าน หมู่ 3",
+            font_size_title,
+            Mm(80.0),
+            Mm(283.0),
+            &bold_font,
+        );
+        current_layer.use_text(
+            format!("ใบจดเลขมิเตอร์ ประจำเดือน {}", for_month),
+            font_size_subtitle,
+            Mm(74.0),
+            Mm(275.0),
+            &bold_font,
+        );
+        current_layer.use_text(
+            format!("โซน {} (แผ่นที่ {}/{})", zone, zone_page, zone_pages),
+            font_size_normal,
+            Mm(TABLE_LEFT),
+            Mm(TABLE_TOP + 3.0),
+            &bold_font,
+        );
+        current_layer.use_text(
+            "ผู้จด ....................................  วันที่จด ....................",
+            font_size_normal,
+            Mm(110.0),
+            Mm(TABLE_TOP + 3.0),
+            &font,
+        );
+
+        // หัวตาราง
+        for (title, x) in COLUMNS.iter() {
+            current_layer.use_text(
+                *title,
+                font_size_normal,
+                Mm(x + 1.5),
+                Mm(TABLE_TOP - ROW_HEIGHT + 3.5),
+                &bold_font,
+            );
+        }
+
+        // แถวข้อมูล พร้อมช่องว่างสำหรับกรอกเลขอ่านครั้งนี้
+        for (j, customer) in customers.iter().enumerate() {
+            log::log_debug(&format!(
+                "โซน {} ลำดับ {}: {} - {}",
+                zone, customer.order, customer.meter_number, customer.name
+            ));
+            let row_bottom = TABLE_TOP - ROW_HEIGHT * (j as f32 + 2.0);
+            let text_y = Mm(row_bottom + 3.5);
+            let cells = [
+                customer.order.to_string(),
+                customer.name.clone(),
+                customer.meter_number.clone(),
+                customer.current_reading.to_string(),
+            ];
+            for ((_, x), cell) in COLUMNS.iter().zip(cells) {
+                current_layer.use_text(cell, font_size_normal, Mm(x + 1.5), text_y, &font);
+            }
+            current_layer.add_line(draw_rect(
+                Mm(COLUMNS[4].1 + 2.0),
+                Mm(row_bottom + 1.5),
+                Mm(COLUMNS[5].1 - COLUMNS[4].1 - 4.0),
+                Mm(ROW_HEIGHT - 3.0),
+            ));
+        }
+
+        // เส้นตาราง
+        let table_bottom = TABLE_TOP - ROW_HEIGHT * (customers.len() as f32 + 1.0);
+        for row in 0..=customers.len() + 1 {
+            let y = Mm(TABLE_TOP - ROW_HEIGHT * row as f32);
+            current_layer.add_line(draw_line(y, Mm(TABLE_LEFT), Mm(TABLE_RIGHT)));
+        }
+        for (_, x) in COLUMNS.iter() {
+            current_layer.add_line(draw_vetical_line(Mm(*x), Mm(TABLE_TOP), Mm(table_bottom)));
+        }
+        current_layer.add_line(draw_vetical_line(
+            Mm(TABLE_RIGHT),
+            Mm(TABLE_TOP),
+            Mm(table_bottom),
+        ));
+    }
+
+    log::log_info(&format!("กำลังบันทึกไฟล์ PDF: {}", output_path));
+    save_pdf(doc, output_path, issue_date)?;
+    log::log_info("บันทึกไฟล์ PDF ใบจดเลขมิเตอร์สำเร็จ!");
+    Ok(())
+}
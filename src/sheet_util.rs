@@ -0,0 +1,92 @@
+use crate::csv_util::deserialize_rows;
+use crate::error::Error;
+use crate::input::{HeaderAliases, ReadReport};
+use crate::log;
+use crate::Result;
+use calamine::{open_workbook_auto, Data, Range, Reader};
+use csv::StringRecord;
+
+// หัวคอลัมน์ที่ใช้หาแถวหัวตาราง (แถวบนๆ มักเป็นชื่อเอกสารหรือหมายเหตุ)
+const HEADER_MARKERS: [&str; 2] = ["ลำดับ", "เลขมิเตอร์"];
+const HEADER_SEARCH_ROWS: usize = 20;
+
+/// อ่านข้อมูลบิลจากไฟล์ Excel (.xlsx, .xls) หรือ OpenDocument (.ods)
+/// ถ้าไม่ระบุชื่อชีต จะใช้ชีตแรกของไฟล์
+pub fn read_sheet_file(
+    file_path: &str,
+    sheet_name: Option<&str>,
+    aliases: &HeaderAliases,
+) -> Result<ReadReport> {
+    log::log_info(&format!("กำลังอ่านไฟล์ตาราง: {}", file_path));
+    let mut workbook = open_workbook_auto(file_path)?;
+
+    let sheet_names = workbook.sheet_names();
+    let sheet = match sheet_name {
+        Some(name) if sheet_names.iter().any(|sheet| sheet == name) => name.to_string(),
+        Some(name) => {
+            return Err(Error::Validation(format!(
+                "ไม่พบชีต \"{}\" ในไฟล์ {} (ชีตที่มี: {})",
+                name,
+                file_path,
+                sheet_names.join(", ")
+            )))
+        }
+        None => sheet_names
+            .first()
+            .cloned()
+            .ok_or_else(|| Error::Validation(format!("ไม่พบชีตในไฟล์ {}", file_path)))?,
+    };
+    log::log_info(&format!("ใช้ชีต: {}", sheet));
+
+    let range = workbook.worksheet_range(&sheet)?;
+    let report = records_from_range(&range, aliases)?;
+    log::log_info(&format!(
+        "อ่านข้อมูลสำเร็จ จำนวน {} รายการ",
+        report.records.len()
+    ));
+    Ok(report)
+}
+
+// หาแถวหัวตาราง แล้วแปลงแถวข้อมูลด้วย serde ตัวเดียวกับไฟล์ CSV
+fn records_from_range(range: &Range<Data>, aliases: &HeaderAliases) -> Result<ReadReport> {
+    let rows: Vec<Vec<String>> = range
+        .rows()
+        .map(|row| row.iter().map(cell_to_string).collect())
+        .collect();
+
+    let header_index = rows
+        .iter()
+        .take(HEADER_SEARCH_ROWS)
+        .position(|row| {
+            HEADER_MARKERS
+                .iter()
+                .all(|marker| row.iter().any(|cell| aliases.canonical(cell) == *marker))
+        })
+        .ok_or_else(|| {
+            Error::Validation("ไม่พบแถวหัวตาราง (ต้องมีคอลัมน์ ลำดับ และ เลขมิเตอร์)".to_string())
+        })?;
+    log::log_debug(&format!("พบหัวตารางที่แถว {}", header_index + 1));
+
+    let headers: StringRecord = rows[header_index]
+        .iter()
+        .map(|cell| aliases.canonical(cell))
+        .collect();
+
+    // เลขแถวตามที่เห็นในโปรแกรมตารางคำนวณ
+    let first_row = range.start().map_or(1, |(row, _)| u64::from(row) + 1);
+    let data_rows = rows
+        .into_iter()
+        .enumerate()
+        .skip(header_index + 1)
+        .map(|(i, row)| (first_row + i as u64, StringRecord::from(row)));
+    Ok(deserialize_rows(&headers, data_rows))
+}
+
+fn cell_to_string(cell: &Data) -> String {
+    match cell {
+        // ตัวเลขในสเปรดชีตเก็บเป็นทศนิยม เช่น 1250.0
+        Data::Float(value) if value.fract() == 0.0 => format!("{}", *value as i64),
+        Data::Empty => String::new(),
+        other => other.to_string().trim().to_string(),
+    }
+}
@@ -0,0 +1,249 @@
+use super::drawing::{draw_line, draw_vetical_line};
+use super::font_util::find_thai_font;
+use super::pdf_util::save_pdf;
+use crate::log;
+use crate::model::{BillRecord, BillSummary};
+use crate::Result;
+use chrono::NaiveDate;
+use printpdf::*;
+
+// ขนาดกระดาษ A4 แนวนอน
+const PAGE_WIDTH: f32 = 297.0;
+const PAGE_HEIGHT: f32 = 210.0;
+
+const TABLE_LEFT: f32 = 10.0;
+const TABLE_RIGHT: f32 = 287.0;
+const TABLE_TOP: f32 = 178.0;
+const ROW_HEIGHT: f32 = 7.0;
+const ROWS_PER_PAGE: usize = 20;
+// พื้นที่ที่ต้องเหลือใต้ตารางสำหรับแถวรวมและช่องลงชื่อ
+const FOOTER_SPACE: f32 = 60.0;
+const PAGE_BOTTOM: f32 = 15.0;
+
+// (หัวคอลัมน์, ตำแหน่ง X เริ่มต้นของคอลัมน์)
+const COLUMNS: [(&str, f32); 9] = [
+    ("ลำดับ", 10.0),
+    ("ชื่อ-นามสกุล", 25.0),
+    ("เลขมิเตอร์", 95.0),
+    ("เลขอ่านครั้งก่อน", 125.0),
+    ("เลขอ่านครั้งหลัง", 155.0),
+    ("หน่วย", 185.0),
+    ("ค่าบำรุง", 207.0),
+    ("ค่าน้ำ", 232.0),
+    ("จำนวนเงิน", 257.0),
+];
+
+pub fn create_summary_pdf(
+    records: &[BillRecord],
+    output_path: &str,
+    for_month: &str,
+    issue_date: NaiveDate,
+) -> Result<()> {
+    log::log_info("เริ่มสร้าง PDF สรุปการเก็บเงินประจำเดือน...");
+
+    log::log_info("สร้างเอกสาร PDF ขนาด A4 แนวนอน (297mm x 210mm)");
+    let (doc, page1, layer1) = PdfDocument::new(
+        "สรุปการเก็บค่าน้ำประปา",
+        Mm(PAGE_WIDTH),
+        Mm(PAGE_HEIGHT),
+        "Layer 1",
+    );
+
+    let (font, bold_font, _italic_font) = find_thai_font(&doc)?;
+    let font_size_normal = 11.0;
+    let font_size_subtitle = 14.0;
+    let font_size_title = 16.0;
+
+    let summary = BillSummary::from_records(records);
+
+    // แบ่งหน้า ถ้าหน้าสุดท้ายไม่มีที่ว่างพอสำหรับยอดรวมและช่องลงชื่อ ให้ขึ้นหน้าใหม่
+    let mut chunks: Vec<&[BillRecord]> = records.chunks(ROWS_PER_PAGE).collect();
+    let last_rows = chunks.last().map_or(0, |chunk| chunk.len());
+    let last_table_bottom = TABLE_TOP - ROW_HEIGHT * (last_rows as f32 + 1.0);
+    if chunks.is_empty() || last_table_bottom - FOOTER_SPACE < PAGE_BOTTOM {
+        chunks.push(&[]);
+    }
+    let page_count = chunks.len();
+    log::log_info(&format!("จำนวนหน้าทั้งหมด: {}", page_count));
+
+    let mut row_index = 0;
+    for (i, chunk) in chunks.iter().enumerate() {
+        let current_layer = if i == 0 {
+            doc.get_page(page1).get_layer(layer1)
+        } else {
+            log::log_debug("สร้างหน้าใหม่");
+            let (page, layer) =
+                doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), format!("Page {}", i + 1));
+            doc.get_page(page).get_layer(layer)
+        };
+
+        // หัวเอกสาร
+        current_layer.use_text(
+            "การประปาหมู่บ้าน หมู่ 3",
+            font_size_title,
+            Mm(123.0),
+            Mm(196.0),
+            &bold_font,
+        );
+        current_layer.use_text(
+            format!("สรุปการเก็บค่าน้ำประปา ประจำเดือน {}", for_month),
+            font_size_subtitle,
+            Mm(112.0),
+            Mm(188.0),
+            &bold_font,
+        );
+        current_layer.use_text(
+            format!("หน้า {}/{}", i + 1, page_count),
+            font_size_normal,
+            Mm(265.0),
+            Mm(196.0),
+            &font,
+        );
+
+        if chunk.is_empty() && i > 0 {
+            draw_totals_and_signatures(
+                &current_layer,
+                &summary,
+                Mm(TABLE_TOP),
+                (&font, &bold_font),
+                font_size_normal,
+            );
+            continue;
+        }
+
+        // หัวตาราง
+        for (title, x) in COLUMNS.iter() {
+            current_layer.use_text(
+                *title,
+                font_size_normal,
+                Mm(x + 1.5),
+                Mm(TABLE_TOP - ROW_HEIGHT + 2.0),
+                &bold_font,
+            );
+        }
+
+        // แถวข้อมูล
+        for (j, bill) in chunk.iter().enumerate() {
+            row_index += 1;
+            log::log_debug(&format!(
+                "สรุปรายการที่ {}: {} - {}",
+                row_index, bill.meter_number, bill.name
+            ));
+            let text_y = Mm(TABLE_TOP - ROW_HEIGHT * (j as f32 + 2.0) + 2.0);
+            let cells = [
+                bill.order.to_string(),
+                bill.name.clone(),
+                bill.meter_number.clone(),
+                bill.previous().to_string(),
+                bill.current_reading.to_string(),
+                bill.units.to_string(),
+                bill.maintenance_fee.to_string(),
+                bill.units_cost().to_string(),
+                bill.total_amount.to_string(),
+            ];
+            for ((_, x), cell) in COLUMNS.iter().zip(cells) {
+                current_layer.use_text(cell, font_size_normal, Mm(x + 1.5), text_y, &font);
+            }
+        }
+
+        // เส้นตาราง
+        let table_bottom = TABLE_TOP - ROW_HEIGHT * (chunk.len() as f32 + 1.0);
+        for row in 0..=chunk.len() + 1 {
+            let y = Mm(TABLE_TOP - ROW_HEIGHT * row as f32);
+            current_layer.add_line(draw_line(y, Mm(TABLE_LEFT), Mm(TABLE_RIGHT)));
+        }
+        for (_, x) in COLUMNS.iter() {
+            current_layer.add_line(draw_vetical_line(Mm(*x), Mm(TABLE_TOP), Mm(table_bottom)));
+        }
+        current_layer.add_line(draw_vetical_line(
+            Mm(TABLE_RIGHT),
+            Mm(TABLE_TOP),
+            Mm(table_bottom),
+        ));
+
+        if i + 1 == page_count {
+            draw_totals_and_signatures(
+                &current_layer,
+                &summary,
+                Mm(table_bottom),
+                (&font, &bold_font),
+                font_size_normal,
+            );
+        }
+    }
+
+    log::log_info(&format!("กำลังบันทึกไฟล์ PDF: {}", output_path));
+    save_pdf(doc, output_path, issue_date)?;
+    log::log_info("บันทึกไฟล์ PDF สรุปสำเร็จ!");
+    Ok(())
+}
+
+// แถวรวม จำนวนบิล และช่องลงชื่อเหรัญญิก/ประธาน ใต้ตาราง
+fn draw_totals_and_signatures(
+    layer: &PdfLayerReference,
+    summary: &BillSummary,
+    top: Mm,
+    (font, bold_font): (&IndirectFontRef, &IndirectFontRef),
+    font_size: f32,
+) {
+    // แถวรวม (หน่วย ค่าบำรุง ค่าน้ำ จำนวนเงิน)
+    let totals_y = top - Mm(ROW_HEIGHT) + Mm(2.0);
+    layer.use_text(
+        "รวมทั้งสิ้น",
+        font_size,
+        Mm(COLUMNS[1].1 + 1.5),
+        totals_y,
+        bold_font,
+    );
+    let totals = [
+        (5, summary.total_units),
+        (6, summary.total_maintenance_fee),
+        (7, summary.total_units_cost),
+        (8, summary.total_amount),
+    ];
+    for (column, value) in totals {
+        layer.use_text(
+            value.to_string(),
+            font_size,
+            Mm(COLUMNS[column].1 + 1.5),
+            totals_y,
+            bold_font,
+        );
+    }
+    layer.add_line(draw_line(
+        top - Mm(ROW_HEIGHT),
+        Mm(TABLE_LEFT),
+        Mm(TABLE_RIGHT),
+    ));
+    layer.add_line(draw_line(
+        top - Mm(ROW_HEIGHT + 0.8),
+        Mm(TABLE_LEFT),
+        Mm(TABLE_RIGHT),
+    ));
+
+    layer.use_text(
+        format!(
+            "จำนวนบิลทั้งหมด {} ใบ   รวมหน่วยที่ใช้ {} หน่วย   ค่าบำรุง {} บาท   รายรับทั้งสิ้น {} บาท",
+            summary.bill_count,
+            summary.total_units,
+            summary.total_maintenance_fee,
+            summary.total_amount
+        ),
+        font_size,
+        Mm(TABLE_LEFT + 1.5),
+        top - Mm(ROW_HEIGHT * 2.5),
+        font,
+    );
+
+    // ช่องลงชื่อ
+    let sign_y = top - Mm(ROW_HEIGHT * 2.5 + 25.0);
+    for (x, role) in [(40.0, "เหรัญญิก"), (180.0, "ประธานกรรมการ")]
+    {
+        layer.use_text("ลงชื่อ", font_size, Mm(x), sign_y, font);
+        layer.add_line(draw_line(sign_y, Mm(x + 12.0), Mm(x + 72.0)));
+        layer.use_text("(", font_size, Mm(x + 10.0), sign_y - Mm(8.0), font);
+        layer.use_text(")", font_size, Mm(x + 73.0), sign_y - Mm(8.0), font);
+        layer.add_line(draw_line(sign_y - Mm(8.0), Mm(x + 12.0), Mm(x + 72.0)));
+        layer.use_text(role, font_size, Mm(x + 30.0), sign_y - Mm(15.0), bold_font);
+    }
+}
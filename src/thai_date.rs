@@ -0,0 +1,340 @@
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+// ผลต่างระหว่างปีพุทธศักราชกับคริสต์ศักราช
+const BE_OFFSET: i32 = 543;
+
+const SHORT_MONTHS: [&str; 12] = [
+    "ม.ค.",
+    "ก.พ.",
+    "มี.ค.",
+    "เม.ย.",
+    "พ.ค.",
+    "มิ.ย.",
+    "ก.ค.",
+    "ส.ค.",
+    "ก.ย.",
+    "ต.ค.",
+    "พ.ย.",
+    "ธ.ค.",
+];
+
+const LONG_MONTHS: [&str; 12] = [
+    "มกราคม",
+    "กุมภาพันธ์",
+    "มีนาคม",
+    "เมษายน",
+    "พฤษภาคม",
+    "มิถุนายน",
+    "กรกฎาคม",
+    "สิงหาคม",
+    "กันยายน",
+    "ตุลาคม",
+    "พฤศจิกายน",
+    "ธันวาคม",
+];
+
+/// รูปแบบชื่อเดือน: ย่อ (ต.ค.) หรือเต็ม (ตุลาคม)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MonthName {
+    Short,
+    Long,
+}
+
+/// ชื่อเดือนภาษาไทย เดือน 1-12
+pub fn month_name(month: u32, style: MonthName) -> &'static str {
+    let names = match style {
+        MonthName::Short => &SHORT_MONTHS,
+        MonthName::Long => &LONG_MONTHS,
+    };
+    names
+        .get(month.wrapping_sub(1) as usize)
+        .copied()
+        .unwrap_or("")
+}
+
+/// ปี พ.ศ. จากปี ค.ศ.
+pub fn buddhist_year(year: i32) -> i32 {
+    year + BE_OFFSET
+}
+
+/// แปลงเลขอารบิกเป็นเลขไทย (๐-๙) ตัวอักษรอื่นคงเดิม
+pub fn to_thai_digits(text: &str) -> String {
+    text.chars()
+        .map(|c| match c.to_digit(10) {
+            Some(digit) if c.is_ascii_digit() => char::from_u32(0x0E50 + digit).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+/// แปลงเลขไทยเป็นเลขอารบิก สำหรับอ่านค่าที่ผู้ใช้พิมพ์ ตัวอักษรอื่นคงเดิม
+pub fn from_thai_digits(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '๐'..='๙' => char::from_u32(c as u32 - 0x0E50 + '0' as u32).unwrap_or(c),
+            c => c,
+        })
+        .collect()
+}
+
+/// วันที่แบบไทย เช่น "05 ต.ค. 2568" หรือ "05 ตุลาคม 2568" เลือกพิมพ์เป็นเลขไทยได้
+pub fn format_date(date: NaiveDate, style: MonthName, thai_digits: bool) -> String {
+    let text = format!(
+        "{} {} {}",
+        date.format("%d"),
+        month_name(date.month(), style),
+        buddhist_year(date.year())
+    );
+    if thai_digits {
+        to_thai_digits(&text)
+    } else {
+        text
+    }
+}
+
+// ปีที่ผู้ใช้พิมพ์: 2 หลักถือเป็น พ.ศ. 25xx, ตั้งแต่ 2400 ขึ้นไปถือเป็น พ.ศ. นอกนั้นเป็น ค.ศ.
+fn parse_year(text: &str) -> Result<i32, String> {
+    let year: i32 = text
+        .parse()
+        .map_err(|_| format!("ปี \"{}\" ไม่ใช่ตัวเลข", text))?;
+    Ok(match year {
+        0..=99 => 2500 + year - BE_OFFSET,
+        2400.. => year - BE_OFFSET,
+        _ => year,
+    })
+}
+
+// ชื่อเดือนแบบย่อหรือเต็ม ไม่สนใจจุด เช่น "ต.ค." "ตค" "ตุลาคม" หรือเลขเดือน
+fn parse_month(text: &str) -> Result<u32, String> {
+    if let Ok(month @ 1..=12) = text.parse::<u32>() {
+        return Ok(month);
+    }
+    let key: String = text.chars().filter(|c| *c != '.').collect();
+    SHORT_MONTHS
+        .iter()
+        .zip(LONG_MONTHS.iter())
+        .position(|(short, long)| short.replace('.', "") == key || *long == key)
+        .map(|i| i as u32 + 1)
+        .ok_or_else(|| format!("ไม่รู้จักชื่อเดือน \"{}\"", text))
+}
+
+/// เดือนที่ออกบิล (ปี ค.ศ. และเดือน) เก็บในไฟล์ประวัติเป็น YYYY-MM
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct YearMonth {
+    pub year: i32,
+    pub month: u32,
+}
+
+impl YearMonth {
+    pub fn from_date(date: NaiveDate) -> Self {
+        YearMonth {
+            year: date.year(),
+            month: date.month(),
+        }
+    }
+
+    /// เลื่อนเดือนไปข้างหน้า (บวก) หรือย้อนหลัง (ลบ)
+    pub fn add_months(self, months: i32) -> Self {
+        let index = self.year * 12 + self.month as i32 - 1 + months;
+        YearMonth {
+            year: index.div_euclid(12),
+            month: index.rem_euclid(12) as u32 + 1,
+        }
+    }
+
+    /// ชื่อเดือนที่พิมพ์บนบิล เช่น "ต.ค. 68"
+    pub fn label(&self) -> String {
+        format!(
+            "{} {:02}",
+            month_name(self.month, MonthName::Short),
+            buddhist_year(self.year) % 100
+        )
+    }
+
+    /// ชื่อเดือนแบบเต็ม เช่น "ตุลาคม 2568"
+    pub fn long_label(&self) -> String {
+        format!(
+            "{} {}",
+            month_name(self.month, MonthName::Long),
+            buddhist_year(self.year)
+        )
+    }
+}
+
+impl fmt::Display for YearMonth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}", self.year, self.month)
+    }
+}
+
+// เก็บในไฟล์ JSON เป็น YYYY-MM เหมือนไฟล์ประวัติ
+impl Serialize for YearMonth {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for YearMonth {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// อ่านได้ทั้ง "2025-10", "ต.ค. 68", "ต.ค. 2568", "ตุลาคม 2568" และเลขไทย
+impl FromStr for YearMonth {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = from_thai_digits(text.trim());
+        let (month, year) =
+            if let Some((year, month)) = text.split_once('-').filter(|(year, _)| year.len() == 4) {
+                (month.trim(), year.trim())
+            } else {
+                text.rsplit_once([' ', '/'])
+                    .map(|(month, year)| (month.trim(), year.trim()))
+                    .ok_or_else(|| {
+                        format!(
+                            "\"{}\" ต้องระบุทั้งเดือนและปี เช่น \"ต.ค. 68\" หรือ \"2025-10\"",
+                            text
+                        )
+                    })?
+            };
+        Ok(YearMonth {
+            year: parse_year(year)?,
+            month: parse_month(month)?,
+        })
+    }
+}
+
+/// อ่านวันที่ได้ทั้ง "2025-10-05", "5 ต.ค. 68", "5 ตุลาคม 2568" และ "5/10/2568"
+pub fn parse_date(text: &str) -> Result<NaiveDate, String> {
+    let text = from_thai_digits(text.trim());
+    if let Ok(date) = NaiveDate::parse_from_str(&text, "%Y-%m-%d") {
+        return Ok(date);
+    }
+
+    let parts: Vec<&str> = text
+        .split([' ', '/'])
+        .filter(|part| !part.is_empty())
+        .collect();
+    let [day, month, year] = parts[..] else {
+        return Err(format!(
+            "\"{}\" ไม่ใช่วันที่ เช่น \"5 ต.ค. 68\" หรือ \"2025-10-05\"",
+            text
+        ));
+    };
+    let day: u32 = day
+        .parse()
+        .map_err(|_| format!("วันที่ \"{}\" ไม่ใช่ตัวเลข", day))?;
+    let year = parse_year(year)?;
+    let month = parse_month(month)?;
+    NaiveDate::from_ymd_opt(year, month, day).ok_or_else(|| {
+        format!(
+            "ไม่มีวันที่ {} ในเดือน {}",
+            day,
+            month_name(month, MonthName::Long)
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn years_are_read_as_buddhist_or_christian_era() {
+        for (text, year) in [
+            ("68", 2025),
+            ("00", 1957),
+            ("99", 2056),
+            ("2568", 2025),
+            ("2400", 1857),
+            ("2025", 2025),
+            ("๖๘", 2025),
+        ] {
+            assert_eq!(parse_year(&from_thai_digits(text)), Ok(year), "{}", text);
+        }
+        for text in ["", "ปี", "68.5"] {
+            assert!(parse_year(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn months_are_read_by_number_or_name() {
+        for (text, month) in [
+            ("1", 1),
+            ("12", 12),
+            ("ต.ค.", 10),
+            ("ตค", 10),
+            ("ตุลาคม", 10),
+            ("ม.ค.", 1),
+            ("ธันวาคม", 12),
+        ] {
+            assert_eq!(parse_month(text), Ok(month), "{}", text);
+        }
+        for text in ["0", "13", "", "ตุลา", "Oct"] {
+            assert!(parse_month(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn year_month_accepts_every_written_form() {
+        let october = YearMonth {
+            year: 2025,
+            month: 10,
+        };
+        for text in [
+            "2025-10",
+            "ต.ค. 68",
+            "ต.ค. 2568",
+            "ตุลาคม 2568",
+            "10/2568",
+            "ต.ค. ๖๘",
+            "๒๐๒๕-๑๐",
+            " ต.ค. 68 ",
+        ] {
+            assert_eq!(text.parse::<YearMonth>(), Ok(october), "{}", text);
+        }
+        for text in ["", "ต.ค.", "2025-13", "2025-00", "13/68", "ตุลา 68"] {
+            assert!(text.parse::<YearMonth>().is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn dates_accept_every_written_form() {
+        let date = NaiveDate::from_ymd_opt(2025, 10, 5).unwrap();
+        for text in [
+            "2025-10-05",
+            "5 ต.ค. 68",
+            "05 ต.ค. 2568",
+            "5 ตุลาคม 2568",
+            "5/10/2568",
+            "5/10/68",
+            "๕ ต.ค. ๖๘",
+            "5/10/2025",
+        ] {
+            assert_eq!(parse_date(text), Ok(date), "{}", text);
+        }
+        for text in [
+            "",
+            "5 ต.ค.",
+            "31 ก.ย. 68",
+            "29 ก.พ. 68",
+            "0 ต.ค. 68",
+            "5 13 68",
+            "ห้า ต.ค. 68",
+        ] {
+            assert!(parse_date(text).is_err(), "{}", text);
+        }
+        // 2567 (2024) เป็นปีอธิกสุรทิน
+        assert_eq!(
+            parse_date("29 ก.พ. 67"),
+            Ok(NaiveDate::from_ymd_opt(2024, 2, 29).unwrap())
+        );
+    }
+}
@@ -0,0 +1,302 @@
+use crate::anomaly::detect_anomalies;
+use crate::error::Error;
+use crate::history::HistoryStore;
+use crate::model::{parse_number, BillRecord, BillSummary};
+use crate::thai_date::YearMonth;
+use crate::Result;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+
+// จำนวนรายที่เลื่อนเมื่อกด PageUp/PageDown
+const PAGE_ROWS: usize = 10;
+
+const HELP: &str = "Enter บันทึกแล้วไปรายถัดไป | ↑/↓ เลือกราย | Ctrl+S บันทึกไฟล์ | Esc ออกโดยไม่บันทึก";
+
+/// ผลการกรอกเลขอ่านมิเตอร์
+pub enum EntryOutcome {
+    /// กรอกครบทุกรายและกดบันทึก ได้ข้อมูลบิลของเดือนนี้ที่คำนวณแล้ว
+    Saved(Vec<BillRecord>),
+    /// ออกโดยไม่บันทึก
+    Cancelled,
+}
+
+/// เปิดหน้าจอกรอกเลขอ่านมิเตอร์ทีละราย เริ่มจากข้อมูลของเดือนก่อน
+/// เลขอ่านครั้งหลังของเดือนก่อนเป็นเลขอ่านครั้งก่อน หน่วยและยอดเงินคำนวณทันทีที่กรอก
+/// และเตือนเลขอ่านที่ผิดปกติด้วยเกณฑ์เดียวกับตอนออกบิล (ดู `anomaly`)
+pub fn enter_readings(
+    previous: &[BillRecord],
+    period: YearMonth,
+    history: Option<&HistoryStore>,
+    anomaly_factor: f64,
+) -> Result<EntryOutcome> {
+    if previous.is_empty() {
+        return Err(Error::Validation(
+            "ไม่มีรายชื่อผู้ใช้น้ำในไฟล์ของเดือนก่อน".to_string(),
+        ));
+    }
+    let mut session = EntrySession::new(previous, period, history, anomaly_factor);
+    let mut terminal = ratatui::try_init()?;
+    let outcome = session.run(&mut terminal);
+    ratatui::restore();
+    outcome
+}
+
+struct EntrySession<'a> {
+    period: YearMonth,
+    history: Option<&'a HistoryStore>,
+    anomaly_factor: f64,
+    records: Vec<BillRecord>,
+    entered: Vec<bool>,
+    // คำเตือนความผิดปกติของแต่ละราย หลังกรอกเลขอ่าน
+    warnings: Vec<Option<&'static str>>,
+    table: TableState,
+    input: String,
+    message: Option<String>,
+    // กด Esc ซ้ำเพื่อยืนยันออกโดยไม่บันทึก
+    confirm_quit: bool,
+}
+
+impl<'a> EntrySession<'a> {
+    fn new(
+        previous: &[BillRecord],
+        period: YearMonth,
+        history: Option<&'a HistoryStore>,
+        anomaly_factor: f64,
+    ) -> Self {
+        let records: Vec<BillRecord> = previous
+            .iter()
+            .map(|record| {
+                let mut record = record.clone();
+                record.previous_reading = Some(record.current_reading);
+                record.recalculate();
+                record
+            })
+            .collect();
+        let count = records.len();
+        EntrySession {
+            period,
+            history,
+            anomaly_factor,
+            records,
+            entered: vec![false; count],
+            warnings: vec![None; count],
+            table: TableState::default().with_selected(Some(0)),
+            input: String::new(),
+            message: None,
+            confirm_quit: false,
+        }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<EntryOutcome> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                if let Some(outcome) = self.handle_key(key) {
+                    return Ok(outcome);
+                }
+            }
+        }
+    }
+
+    fn selected(&self) -> usize {
+        self.table.selected().unwrap_or(0)
+    }
+
+    // เลือกรายที่ `index` ช่องกรอกว่างเสมอ กด Enter โดยไม่กรอกจะคงเลขอ่านเดิมไว้
+    fn select(&mut self, index: usize) {
+        let index = index.min(self.records.len() - 1);
+        self.table.select(Some(index));
+        self.input.clear();
+        self.message = None;
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Option<EntryOutcome> {
+        if key.code != KeyCode::Esc {
+            self.confirm_quit = false;
+        }
+        let index = self.selected();
+        match key.code {
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return self.save();
+            }
+            KeyCode::F(2) => return self.save(),
+            KeyCode::Esc => {
+                if self.confirm_quit || !self.entered.contains(&true) {
+                    return Some(EntryOutcome::Cancelled);
+                }
+                self.confirm_quit = true;
+                self.message = Some("กด Esc อีกครั้งเพื่อออกโดยไม่บันทึก".to_string());
+            }
+            KeyCode::Up => self.select(index.saturating_sub(1)),
+            KeyCode::Down => self.select(index + 1),
+            KeyCode::PageUp => self.select(index.saturating_sub(PAGE_ROWS)),
+            KeyCode::PageDown => self.select(index + PAGE_ROWS),
+            KeyCode::Home => self.select(0),
+            KeyCode::End => self.select(self.records.len() - 1),
+            KeyCode::Enter => self.commit(index),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Char(c) if c.is_ascii_digit() || ('๐'..='๙').contains(&c) || c == ',' => {
+                self.input.push(c);
+            }
+            _ => {}
+        }
+        None
+    }
+
+    // บันทึกเลขอ่านที่กรอก คำนวณหน่วยและยอดเงิน ตรวจความผิดปกติ แล้วไปรายถัดไป
+    fn commit(&mut self, index: usize) {
+        match parse_number(&self.input) {
+            Ok(Some(reading)) => {
+                let record = &mut self.records[index];
+                record.current_reading = reading;
+                record.recalculate();
+                self.entered[index] = true;
+                self.warnings[index] = self.check(index);
+                let warning = self.warnings[index]
+                    .map(|warning| format!("ลำดับ {}: {}", self.records[index].order, warning));
+                self.select(index + 1);
+                self.message = warning;
+            }
+            // ช่องว่าง: คงเลขอ่านเดิม (หรือข้ามไปก่อนถ้ายังไม่ได้กรอก)
+            Ok(None) => self.select(index + 1),
+            Err(error) => {
+                self.message = Some(format!("เลขอ่าน \"{}\" {}", self.input, error));
+            }
+        }
+    }
+
+    fn check(&self, index: usize) -> Option<&'static str> {
+        detect_anomalies(
+            std::slice::from_ref(&self.records[index]),
+            self.history,
+            self.period,
+            self.anomaly_factor,
+        )
+        .first()
+        .map(|anomaly| anomaly.kind.description())
+    }
+
+    // บันทึกได้เมื่อกรอกครบทุกราย ถ้ายังไม่ครบจะไปที่รายแรกที่ยังไม่ได้กรอก
+    fn save(&mut self) -> Option<EntryOutcome> {
+        let missing = self.entered.iter().filter(|entered| !**entered).count();
+        match self.entered.iter().position(|entered| !entered) {
+            Some(first) => {
+                self.select(first);
+                self.message = Some(format!("ยังไม่ได้กรอกเลขอ่าน {} ราย", missing));
+                None
+            }
+            None => Some(EntryOutcome::Saved(self.records.clone())),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [header_area, table_area, input_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(5),
+        ])
+        .areas(frame.area());
+
+        let entered_count = self.entered.iter().filter(|entered| **entered).count();
+        let summary = BillSummary::from_records(
+            self.records
+                .iter()
+                .zip(&self.entered)
+                .filter(|(_, entered)| **entered)
+                .map(|(record, _)| record),
+        );
+        let header = Paragraph::new(format!(
+            "กรอกแล้ว {}/{} ราย   รวม {} หน่วย   ยอดเงิน {} บาท",
+            entered_count,
+            self.records.len(),
+            summary.total_units,
+            summary.total_amount
+        ))
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            " จดเลขมิเตอร์ ประจำเดือน {} ",
+            self.period.long_label()
+        )));
+        frame.render_widget(header, header_area);
+
+        let rows = self.records.iter().enumerate().map(|(i, record)| {
+            let (current, units, amount) = if self.entered[i] {
+                (
+                    record.current_reading.to_string(),
+                    record.units.to_string(),
+                    record.total_amount.to_string(),
+                )
+            } else {
+                ("-".to_string(), String::new(), String::new())
+            };
+            let row = Row::new([
+                record.order.to_string(),
+                record.name.clone(),
+                record.meter_number.clone(),
+                record.previous().to_string(),
+                current,
+                units,
+                amount,
+                self.warnings[i].unwrap_or_default().to_string(),
+            ]);
+            if self.warnings[i].is_some() {
+                row.style(Style::default().fg(Color::Yellow))
+            } else {
+                row
+            }
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(6),
+                Constraint::Min(16),
+                Constraint::Length(12),
+                Constraint::Length(10),
+                Constraint::Length(10),
+                Constraint::Length(6),
+                Constraint::Length(8),
+                Constraint::Min(18),
+            ],
+        )
+        .header(
+            Row::new([
+                "ลำดับ",
+                "ชื่อ",
+                "เลขมิเตอร์",
+                "ครั้งก่อน",
+                "ครั้งนี้",
+                "หน่วย",
+                "จำนวนเงิน",
+                "หมายเหตุ",
+            ])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL));
+        frame.render_stateful_widget(table, table_area, &mut self.table);
+
+        let record = &self.records[self.selected()];
+        let message = self.message.clone().unwrap_or_default();
+        let input = Paragraph::new(vec![
+            Line::from(format!(
+                "เลขอ่านครั้งนี้ของ {} ({}) เลขอ่านครั้งก่อน {}: {}_",
+                record.name,
+                record.meter_number,
+                record.previous(),
+                self.input
+            )),
+            Line::styled(message, Style::default().fg(Color::Yellow)),
+            Line::from(HELP),
+        ])
+        .block(Block::default().borders(Borders::ALL));
+        frame.render_widget(input, input_area);
+    }
+}
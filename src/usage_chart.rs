@@ -0,0 +1,254 @@
+use super::drawing::{draw_filled_rect, draw_line};
+use crate::error::Error;
+use crate::history::HistoryStore;
+use crate::log;
+use crate::model::BillRecord;
+use crate::thai_date::{month_name, MonthName, YearMonth};
+use crate::Result;
+use printpdf::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const FONT_SIZE_TITLE: f32 = 8.0;
+const FONT_SIZE_LABEL: f32 = 6.0;
+// ความกว้างของหัวกราฟทางซ้ายของแท่ง (มม.)
+const TITLE_WIDTH: f32 = 22.0;
+// สีแท่งกราฟ: เดือนที่ออกบิลเข้มกว่าเดือนก่อนหน้า
+const BAR_GREY: f32 = 0.65;
+const CURRENT_BAR_GREY: f32 = 0.3;
+
+/// จำนวนเดือนน้อยที่สุดและมากที่สุดของกราฟ (แท่งต้องกว้างพอให้อ่านตัวเลขบนแท่งได้)
+pub const MIN_MONTHS: usize = 6;
+pub const MAX_MONTHS: usize = 12;
+
+/// หน่วยที่ใช้ย้อนหลังของแต่ละมิเตอร์ สำหรับวาดกราฟแท่งบนบิล
+#[derive(Debug, Clone)]
+pub struct UsageHistory {
+    /// เดือนที่แสดงในกราฟ เรียงจากเก่าไปใหม่ เดือนสุดท้ายคือเดือนที่ออกบิล
+    months: Vec<YearMonth>,
+    units: HashMap<String, Vec<Option<u32>>>,
+}
+
+/// กราฟหน่วยที่ใช้ของมิเตอร์เดียว เก็บไว้ในทะเบียนบิลเพื่อพิมพ์ซ้ำได้เหมือนต้นฉบับ
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MeterUsage {
+    /// เดือนที่แสดงในกราฟ เรียงจากเก่าไปใหม่
+    pub months: Vec<YearMonth>,
+    /// หน่วยที่ใช้ของแต่ละเดือน (ว่างถ้าไม่มีข้อมูลเดือนนั้น)
+    pub units: Vec<Option<u32>>,
+}
+
+impl UsageHistory {
+    /// หน่วยที่ใช้ `months` เดือนล่าสุดจนถึงเดือนที่ออกบิล
+    /// เดือนก่อนหน้ามาจากไฟล์ประวัติ ส่วนเดือนนี้มาจาก `records`
+    /// `months` ต้องอยู่ระหว่าง [`MIN_MONTHS`] ถึง [`MAX_MONTHS`]
+    pub fn build(
+        history: &HistoryStore,
+        period: YearMonth,
+        months: usize,
+        records: &[BillRecord],
+    ) -> Result<Self> {
+        check_months(months)?;
+        let window: Vec<YearMonth> = (0..months as i32)
+            .rev()
+            .map(|back| period.add_months(-back))
+            .collect();
+        let mut units: HashMap<String, Vec<Option<u32>>> = HashMap::new();
+        let mut set_units = |meter_number: &str, month: YearMonth, value: u32| {
+            if let Some(i) = window.iter().position(|m| *m == month) {
+                units
+                    .entry(meter_number.trim().to_string())
+                    .or_insert_with(|| vec![None; window.len()])[i] = Some(value);
+            }
+        };
+        for (month, meter_number, value) in history.monthly_units() {
+            if month < period {
+                set_units(meter_number, month, value);
+            }
+        }
+        for record in records {
+            set_units(&record.meter_number, period, record.units);
+        }
+        log::log_debug(&format!(
+            "ข้อมูลกราฟหน่วยที่ใช้ {} เดือน ({} ถึง {}) จำนวน {} มิเตอร์",
+            window.len(),
+            window.first().map(|m| m.to_string()).unwrap_or_default(),
+            period,
+            units.len()
+        ));
+        Ok(UsageHistory {
+            months: window,
+            units,
+        })
+    }
+
+    /// จำนวนเดือนที่แสดงในกราฟ
+    pub fn months(&self) -> usize {
+        self.months.len()
+    }
+
+    /// กราฟของมิเตอร์เดียว สำหรับบันทึกในทะเบียนบิล
+    pub fn meter_usage(&self, meter_number: &str) -> MeterUsage {
+        MeterUsage {
+            months: self.months.clone(),
+            units: self
+                .units
+                .get(meter_number.trim())
+                .cloned()
+                .unwrap_or_else(|| vec![None; self.months.len()]),
+        }
+    }
+
+    /// กราฟที่บันทึกไว้ในทะเบียนบิล สำหรับพิมพ์ซ้ำโดยไม่อ่านไฟล์ประวัติใหม่
+    /// (ประวัติอาจเปลี่ยนไปแล้วหลังออกบิล)
+    pub fn from_meter_usage(meter_number: &str, usage: MeterUsage) -> Result<Self> {
+        check_months(usage.months.len())?;
+        if usage.units.len() != usage.months.len() {
+            return Err(Error::Validation(format!(
+                "กราฟหน่วยที่ใช้ในทะเบียนบิลมี {} เดือน แต่มีหน่วยที่ใช้ {} เดือน",
+                usage.months.len(),
+                usage.units.len()
+            )));
+        }
+        let mut units = HashMap::new();
+        // มิเตอร์ที่ไม่มีข้อมูลเลยไม่วาดกราฟ เหมือนตอนออกบิล
+        if usage.units.iter().any(Option::is_some) {
+            units.insert(meter_number.trim().to_string(), usage.units);
+        }
+        Ok(UsageHistory {
+            months: usage.months,
+            units,
+        })
+    }
+
+    /// วาดกราฟแท่งของมิเตอร์ในกรอบที่มีมุมซ้ายล่างที่ (x, y) หัวกราฟอยู่ด้านซ้าย
+    /// ชื่อเดือนอยู่ใต้แท่ง จำนวนหน่วยอยู่บนแท่ง
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &self,
+        layer: &PdfLayerReference,
+        (font, bold_font): (&IndirectFontRef, &IndirectFontRef),
+        meter_number: &str,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    ) {
+        let Some(units) = self.units.get(meter_number.trim()) else {
+            log::log_debug(&format!("ไม่มีประวัติหน่วยที่ใช้ของมิเตอร์ {}", meter_number));
+            return;
+        };
+        let max_units = units.iter().flatten().copied().max().unwrap_or(0).max(1);
+
+        // หัวกราฟสองบรรทัดทางซ้าย กราฟจึงเตี้ยพอจะวางข้างตารางค่าน้ำได้
+        layer.use_text(
+            "หน่วยที่ใช้",
+            FONT_SIZE_TITLE,
+            Mm(x),
+            Mm(y + height - 3.0),
+            bold_font,
+        );
+        layer.use_text(
+            format!("ย้อนหลัง {} เดือน", self.months.len()),
+            FONT_SIZE_TITLE,
+            Mm(x),
+            Mm(y + height - 6.5),
+            bold_font,
+        );
+
+        // แกนนอนอยู่เหนือชื่อเดือน แท่งสูงสุดเว้นที่ให้ตัวเลขบนแท่ง
+        let x = x + TITLE_WIDTH;
+        let width = width - TITLE_WIDTH;
+        let axis_y = y + 3.0;
+        let max_bar_height = height - 6.0;
+        let slot = width / self.months.len() as f32;
+        layer.add_line(draw_line(Mm(axis_y), Mm(x), Mm(x + width)));
+
+        let last = self.months.len() - 1;
+        for (i, (month, value)) in self.months.iter().zip(units).enumerate() {
+            let slot_x = x + slot * i as f32;
+            layer.use_text(
+                month_name(month.month, MonthName::Short),
+                FONT_SIZE_LABEL,
+                Mm(slot_x + 0.5),
+                Mm(y + 0.8),
+                font,
+            );
+            let Some(value) = value else {
+                continue;
+            };
+
+            let bar_height = max_bar_height * *value as f32 / max_units as f32;
+            let grey = if i == last {
+                CURRENT_BAR_GREY
+            } else {
+                BAR_GREY
+            };
+            layer.set_fill_color(Color::Greyscale(Greyscale::new(grey, None)));
+            layer.add_polygon(draw_filled_rect(
+                Mm(slot_x + slot * 0.2),
+                Mm(axis_y),
+                Mm(slot * 0.6),
+                Mm(bar_height),
+            ));
+            // ตั้งสีกลับเป็นสีดำสำหรับตัวอักษร
+            layer.set_fill_color(Color::Greyscale(Greyscale::new(0.0, None)));
+            layer.use_text(
+                value.to_string(),
+                FONT_SIZE_LABEL,
+                Mm(slot_x + slot * 0.2),
+                Mm(axis_y + bar_height + 0.8),
+                font,
+            );
+        }
+    }
+}
+
+fn check_months(months: usize) -> Result<()> {
+    if !(MIN_MONTHS..=MAX_MONTHS).contains(&months) {
+        return Err(Error::Validation(format!(
+            "กราฟหน่วยที่ใช้ต้องมี {}-{} เดือน (ระบุมา {} เดือน)",
+            MIN_MONTHS, MAX_MONTHS, months
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history() -> HistoryStore {
+        HistoryStore::load("tests/ไม่มีไฟล์นี้.csv").unwrap()
+    }
+
+    #[test]
+    fn month_count_outside_the_range_is_rejected() {
+        let period: YearMonth = "2025-10".parse().unwrap();
+        for months in [0, 1, MIN_MONTHS - 1, MAX_MONTHS + 1, usize::MAX] {
+            assert!(
+                UsageHistory::build(&history(), period, months, &[]).is_err(),
+                "{} เดือน",
+                months
+            );
+        }
+        for months in [MIN_MONTHS, MAX_MONTHS] {
+            let chart = UsageHistory::build(&history(), period, months, &[]).unwrap();
+            assert_eq!(chart.months(), months);
+        }
+    }
+
+    #[test]
+    fn stored_chart_must_match_its_months() {
+        let period: YearMonth = "2025-10".parse().unwrap();
+        let chart = UsageHistory::build(&history(), period, 6, &[]).unwrap();
+        let mut usage = chart.meter_usage("M001");
+        assert!(UsageHistory::from_meter_usage("M001", usage.clone()).is_ok());
+
+        usage.units.pop();
+        assert!(UsageHistory::from_meter_usage("M001", usage.clone()).is_err());
+        usage.months.clear();
+        usage.units.clear();
+        assert!(UsageHistory::from_meter_usage("M001", usage).is_err());
+    }
+}
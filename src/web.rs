@@ -0,0 +1,794 @@
+use crate::billing::{prepare_bills, BillingSettings, MonthlyBills};
+use crate::error::Error;
+use crate::input::{read_records, HeaderAliases, InputOptions, ReadReport};
+use crate::json_util::records_from_json;
+use crate::log;
+use crate::model::{BillSummary, ComputedBill};
+use crate::pdf_util::{create_pdf_bytes, per_customer_path, BillOptions, CopyMode};
+use crate::thai_date::{parse_date, YearMonth};
+use crate::Result;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Cursor, Read, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tiny_http::{Header, Method, Request, Response, Server};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+// หน้าเว็บภาษาไทยสำหรับอัปโหลดไฟล์ ตรวจข้อมูล และดาวน์โหลดใบเสร็จ
+const INDEX_HTML: &str = include_str!("web/index.html");
+// ขนาดไฟล์ที่อัปโหลดได้ (ไฟล์ข้อมูลหมู่บ้านเล็กกว่านี้มาก)
+const MAX_UPLOAD_BYTES: u64 = 20 * 1024 * 1024;
+// จำนวนครั้งที่ลองตั้งชื่อไฟล์ชั่วคราวใหม่เมื่อชื่อซ้ำกับไฟล์ที่มีอยู่
+const UPLOAD_FILE_ATTEMPTS: u32 = 16;
+// ชื่อไฟล์แต่ละบิลใน zip ของ /api/bills
+const DEFAULT_NAME_PATTERN: &str = "{order}-{meter}.pdf";
+
+type HttpResponse = Response<Cursor<Vec<u8>>>;
+
+/// เปิดหน้าเว็บและ JSON API ที่ `address` (เช่น 127.0.0.1:8080) และรอรับคำขอจนกว่าจะปิดโปรแกรม
+/// ออกบิลด้วยขั้นตอนเดียวกับบรรทัดคำสั่ง (`billing`): ยกเลขอ่านจากไฟล์ประวัติ ตรวจเลขอ่านผิดปกติ
+/// แล้วบันทึกประวัติและทะเบียนบิลเมื่อสร้างใบเสร็จ
+///
+/// - `GET /` หน้าเว็บ, `POST /validate` และ `POST /bills` รับไฟล์ที่อัปโหลด
+/// - `GET /api/profiles` ชุดตัวเลือกใบเสร็จ (`profiles`) ให้เลือกในหน้าเว็บ
+/// - `POST /api/validate`, `POST /api/summary` และ `POST /api/bills` รับ JSON (ดู README)
+pub fn serve(
+    address: &str,
+    aliases: HeaderAliases,
+    billing: BillingSettings,
+    profiles: Vec<Profile>,
+) -> Result<()> {
+    let server = Server::http(address)
+        .map_err(|error| Error::Network(format!("เปิดหน้าเว็บที่ {} ไม่ได้: {}", address, error)))?;
+    log::log_info(&format!("เปิดหน้าเว็บที่ http://{}/ (กด Ctrl+C เพื่อปิด)", address));
+
+    let hosts = allowed_hosts(address);
+    for mut request in server.incoming_requests() {
+        log::log_info(&format!("{} {}", request.method(), request.url()));
+        let api = request.url().starts_with("/api/");
+        let response = match check_request_source(&request, hosts.as_deref()) {
+            Ok(()) => handle(&mut request, &aliases, &billing, &profiles).unwrap_or_else(|error| {
+                log::log_error(&error.to_string());
+                error_response(api, status_code(&error), &error)
+            }),
+            Err(message) => {
+                log::log_warn(&format!("ปฏิเสธคำขอ: {}", message));
+                error_response(api, 403, &Error::Validation(message))
+            }
+        };
+        if let Err(error) = request.respond(response) {
+            log::log_warn(&format!("ส่งข้อมูลกลับไม่สำเร็จ: {}", error));
+        }
+    }
+    Ok(())
+}
+
+fn handle(
+    request: &mut Request,
+    aliases: &HeaderAliases,
+    billing: &BillingSettings,
+    profiles: &[Profile],
+) -> Result<HttpResponse> {
+    let (path, query) = parse_url(request.url());
+    match (request.method(), path.as_str()) {
+        (Method::Get, "/") => Ok(Response::from_string(INDEX_HTML)
+            .with_header(header("Content-Type", "text/html; charset=utf-8"))),
+        (Method::Get, "/api/profiles") => Ok(json_response(&json!({ "profiles": profiles }))),
+        (Method::Post, "/validate") => {
+            let report = read_upload(request, &query, aliases)?;
+            Ok(json_response(&validation_json(&report)))
+        }
+        (Method::Post, "/bills") => {
+            let report = read_upload(request, &query, aliases)?;
+            let settings = BillSettings::from_query(&query)?.with_profile(profiles)?;
+            bills_response(report, &settings, billing)
+        }
+        (Method::Post, "/api/validate") => {
+            let (_, report) = read_api_request(request, profiles)?;
+            Ok(json_response(&validation_json(&report)))
+        }
+        (Method::Post, "/api/summary") => {
+            let (api_request, report) = read_api_request(request, profiles)?;
+            Ok(json_response(&summary_json(
+                report,
+                &api_request.settings,
+                billing,
+            )?))
+        }
+        (Method::Post, "/api/bills") => {
+            let (api_request, report) = read_api_request(request, profiles)?;
+            match api_request.format {
+                ApiFormat::Pdf => bills_response(report, &api_request.settings, billing),
+                ApiFormat::Zip => zip_response(
+                    report,
+                    &api_request.settings,
+                    billing,
+                    api_request
+                        .name_pattern
+                        .as_deref()
+                        .unwrap_or(DEFAULT_NAME_PATTERN),
+                ),
+            }
+        }
+        _ => Ok(text_response(404, "ไม่พบหน้าที่ต้องการ")),
+    }
+}
+
+fn error_response(api: bool, status: u16, error: &Error) -> HttpResponse {
+    if api {
+        // โปรแกรมที่เรียก API ใช้รหัสเดียวกับรหัสจบการทำงานของบรรทัดคำสั่ง
+        let body = json!({ "error": error.to_string(), "code": error.exit_code() });
+        json_response(&body).with_status_code(status)
+    } else {
+        text_response(status, &error.to_string())
+    }
+}
+
+// ชื่อเครื่อง:พอร์ต ที่ยอมรับในหัว Host ของคำขอ กันเว็บอื่นใช้ชื่อโดเมนของตัวเองชี้มาที่เครื่องนี้ (DNS rebinding)
+// เปิดทุก interface (0.0.0.0) ไม่รู้ชื่อที่เครื่องอื่นใช้เรียก จึงคืนค่า None และตรวจเฉพาะ Origin
+fn allowed_hosts(address: &str) -> Option<Vec<String>> {
+    let address = address.to_ascii_lowercase();
+    let (host, port) = match address.parse::<SocketAddr>() {
+        Ok(socket) if socket.ip().is_unspecified() => return None,
+        Ok(socket) => (
+            if socket.ip().is_loopback() {
+                "localhost".to_string()
+            } else {
+                socket.ip().to_string()
+            },
+            socket.port(),
+        ),
+        Err(_) => match address.rsplit_once(':') {
+            Some((host, port)) => (host.to_string(), port.parse().unwrap_or(80)),
+            None => (address.clone(), 80),
+        },
+    };
+    let names = if host == "localhost" {
+        vec![
+            "localhost".to_string(),
+            "127.0.0.1".to_string(),
+            "[::1]".to_string(),
+        ]
+    } else if host.contains(':') {
+        vec![format!("[{}]", host)]
+    } else {
+        vec![host]
+    };
+    let mut hosts: Vec<String> = names
+        .iter()
+        .map(|name| format!("{}:{}", name, port))
+        .collect();
+    // พอร์ต 80 เบราว์เซอร์ไม่ใส่พอร์ตในหัว Host
+    if port == 80 {
+        hosts.extend(names);
+    }
+    Some(hosts)
+}
+
+// คำขอ POST ต้องมาจากหน้าเว็บนี้หรือโปรแกรมที่เรียก API โดยตรง ไม่ใช่เว็บอื่นที่เปิดอยู่ในเบราว์เซอร์
+// เว็บอื่นส่งฟอร์มข้ามมาได้เฉพาะ text/plain หรือ form โดยไม่ผ่านการขออนุญาต (preflight) และใส่หัวอื่นเองไม่ได้
+// จึงให้ /api/ ต้องเป็น application/json ส่วนการอัปโหลดไฟล์ต้องมีหัว X-Requested-With
+fn check_request_source(
+    request: &Request,
+    hosts: Option<&[String]>,
+) -> std::result::Result<(), String> {
+    if *request.method() != Method::Post {
+        return Ok(());
+    }
+    let header_value = |name: &'static str| {
+        request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv(name))
+            .map(|header| header.value.as_str().trim().to_ascii_lowercase())
+    };
+
+    let host = header_value("Host").ok_or("ไม่มีหัว Host")?;
+    if let Some(hosts) = hosts {
+        if !hosts.contains(&host) {
+            return Err(format!("ชื่อเครื่อง \"{}\" ไม่ใช่ที่อยู่ของหน้าเว็บนี้", host));
+        }
+    }
+    if let Some(origin) = header_value("Origin") {
+        if origin != format!("http://{}", host) {
+            return Err(format!("คำขอมาจากเว็บอื่น ({})", origin));
+        }
+    }
+
+    if request.url().starts_with("/api/") {
+        let content_type = header_value("Content-Type").unwrap_or_default();
+        let media_type = content_type.split(';').next().unwrap_or_default().trim();
+        if media_type != "application/json" {
+            return Err("API ต้องส่งข้อมูลแบบ Content-Type: application/json".to_string());
+        }
+    } else if header_value("X-Requested-With").is_none_or(|value| value.is_empty()) {
+        return Err("ต้องส่งจากหน้าเว็บของโปรแกรม".to_string());
+    }
+    Ok(())
+}
+
+// ข้อมูลที่ผู้ใช้ส่งมาผิดได้รหัส 400 นอกนั้นเป็นปัญหาของเครื่องที่เปิดหน้าเว็บ
+fn status_code(error: &Error) -> u16 {
+    match error {
+        Error::Validation(_) | Error::Csv(_) | Error::Sheet(_) | Error::Json(_) => 400,
+        _ => 500,
+    }
+}
+
+// ชื่อ Content-Type และค่าที่ใช้เป็นตัวอักษร ASCII ที่กำหนดไว้ในโปรแกรมเท่านั้น
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("HTTP header ต้องเป็น ASCII")
+}
+
+fn text_response(status: u16, text: &str) -> HttpResponse {
+    Response::from_string(text)
+        .with_status_code(status)
+        .with_header(header("Content-Type", "text/plain; charset=utf-8"))
+}
+
+fn json_response(value: &serde_json::Value) -> HttpResponse {
+    Response::from_string(value.to_string())
+        .with_header(header("Content-Type", "application/json; charset=utf-8"))
+}
+
+// แยก path และ query string (ค่าที่เข้ารหัส %XX และ + แปลงกลับแล้ว)
+fn parse_url(url: &str) -> (String, HashMap<String, String>) {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect();
+    (path.to_string(), query)
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    Err(_) => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// ค่าช่องทำเครื่องหมายในฟอร์ม
+fn query_flag(query: &HashMap<String, String>, key: &str) -> bool {
+    query
+        .get(key)
+        .is_some_and(|value| matches!(value.as_str(), "1" | "on" | "true"))
+}
+
+fn read_body(request: &mut Request) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(MAX_UPLOAD_BYTES + 1)
+        .read_to_end(&mut body)?;
+    if body.len() as u64 > MAX_UPLOAD_BYTES {
+        return Err(Error::Validation(format!(
+            "ข้อมูลใหญ่เกิน {} MB",
+            MAX_UPLOAD_BYTES / 1024 / 1024
+        )));
+    }
+    Ok(body)
+}
+
+// เขียนไฟล์ที่อัปโหลดลงไฟล์ชั่วคราวด้วยนามสกุลเดิม แล้วอ่านด้วยตัวอ่านเดียวกับบรรทัดคำสั่ง
+fn read_upload(
+    request: &mut Request,
+    query: &HashMap<String, String>,
+    aliases: &HeaderAliases,
+) -> Result<ReadReport> {
+    let file_name = query
+        .get("file")
+        .map(String::as_str)
+        .unwrap_or("upload.csv");
+    let extension = Path::new(file_name)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .filter(|ext| ext.chars().all(|c| c.is_ascii_alphanumeric()))
+        .unwrap_or_else(|| "csv".to_string());
+
+    let body = read_body(request)?;
+    if body.is_empty() {
+        return Err(Error::Validation("ไม่ได้เลือกไฟล์ข้อมูล".to_string()));
+    }
+
+    let upload_path = create_upload_file(&extension, &body)?;
+    let options = InputOptions {
+        sheet: query
+            .get("sheet")
+            .filter(|sheet| !sheet.is_empty())
+            .cloned(),
+        aliases: aliases.clone(),
+    };
+    let report = read_records(&upload_path.to_string_lossy(), &options);
+    if let Err(error) = fs::remove_file(&upload_path) {
+        log::log_debug(&format!("ลบไฟล์ชั่วคราวไม่ได้: {}", error));
+    }
+    let report = report?;
+    report.log_errors(file_name);
+    Ok(report)
+}
+
+// สร้างไฟล์ชั่วคราวชื่อไม่ซ้ำ create_new จะไม่เขียนทับหรือตามลิงก์ไปยังไฟล์ที่มีอยู่แล้ว
+fn create_upload_file(extension: &str, body: &[u8]) -> Result<PathBuf> {
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    for attempt in 0..UPLOAD_FILE_ATTEMPTS {
+        let path = std::env::temp_dir().join(format!(
+            "papa_moo_3_bills_upload_{}_{}_{}.{}",
+            std::process::id(),
+            stamp,
+            attempt,
+            extension
+        ));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        // ไฟล์มีข้อมูลผู้ใช้น้ำ ให้อ่านได้เฉพาะผู้ใช้ที่รันโปรแกรม
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        match options.open(&path) {
+            Ok(mut file) => {
+                if let Err(error) = file.write_all(body) {
+                    let _ = fs::remove_file(&path);
+                    return Err(error.into());
+                }
+                return Ok(path);
+            }
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(error.into()),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "สร้างไฟล์ชั่วคราวสำหรับไฟล์ที่อัปโหลดไม่ได้",
+    )
+    .into())
+}
+
+// ผลตรวจข้อมูลสำหรับแสดงในหน้าเว็บก่อนสร้างใบเสร็จ
+fn validation_json(report: &ReadReport) -> serde_json::Value {
+    let summary = BillSummary::from_records(&report.records);
+    let errors: Vec<_> = report
+        .errors
+        .iter()
+        .map(|error| {
+            json!({
+                "line": error.line,
+                "column": error.column,
+                "value": error.value,
+                "message": error.message,
+            })
+        })
+        .collect();
+    json!({
+        "records": report.records.len(),
+        "total_units": summary.total_units,
+        "total_amount": summary.total_amount,
+        "errors": errors,
+    })
+}
+
+/// ชุดตัวเลือกใบเสร็จที่ตั้งชื่อไว้ในไฟล์ `--profiles` เช่น "ใบเสร็จพร้อมสำเนา"
+/// เลือกในหน้าเว็บหรือใส่ `"profile"` ใน JSON ของ API แทนการกำหนดตัวเลือกทุกครั้ง
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Profile {
+    name: String,
+    #[serde(flatten)]
+    settings: BillSettings,
+}
+
+/// อ่านไฟล์โปรไฟล์ (JSON array ของ `{"name": ..., ตัวเลือกใบเสร็จ}`) เรียงตามลำดับในไฟล์
+pub fn load_profiles(path: &str) -> Result<Vec<Profile>> {
+    let profiles: Vec<Profile> = serde_json::from_slice(&fs::read(path)?)?;
+    let mut names = HashSet::new();
+    for profile in &profiles {
+        if !names.insert(profile.name.as_str()) {
+            return Err(Error::Validation(format!(
+                "ชื่อโปรไฟล์ \"{}\" ซ้ำกันในไฟล์ {}",
+                profile.name, path
+            )));
+        }
+        // ตรวจตัวเลือกตั้งแต่เปิดหน้าเว็บ ไม่ใช่ตอนออกบิล
+        profile.settings.bill_options()?;
+    }
+    log::log_info(&format!("อ่านโปรไฟล์ {} ชุด ({})", profiles.len(), path));
+    Ok(profiles)
+}
+
+/// ตัวเลือกใบเสร็จ จากฟอร์มในหน้าเว็บ (query string) หรือ JSON ของ API
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct BillSettings {
+    /// ชื่อโปรไฟล์ ตัวเลือกที่ไม่ได้กำหนดมาใช้ค่าของโปรไฟล์
+    #[serde(skip_serializing)]
+    profile: Option<String>,
+    /// ประจำเดือน เช่น "ต.ค. 68" หรือ "2025-10" (ค่าเริ่มต้นคือเดือนของวันออกบิล)
+    month: Option<String>,
+    /// วันออกบิล เช่น "2025-10-05" หรือ "5 ต.ค. 68" (ค่าเริ่มต้นคือวันนี้)
+    issue_date: Option<String>,
+    /// single, same-sheet หรือ separate
+    copies: Option<String>,
+    due_days: Option<u32>,
+    payment_stub: bool,
+    thai_digits: bool,
+    skip_invalid: bool,
+}
+
+impl BillSettings {
+    fn from_query(query: &HashMap<String, String>) -> Result<Self> {
+        let value = |key: &str| {
+            query
+                .get(key)
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        let due_days = match value("due_days") {
+            Some(text) => Some(text.parse().map_err(|_| {
+                Error::Validation(format!("จำนวนวันครบกำหนดชำระ \"{}\" ไม่ใช่ตัวเลข", text))
+            })?),
+            None => None,
+        };
+        Ok(BillSettings {
+            profile: value("profile"),
+            month: value("month"),
+            issue_date: value("issue_date"),
+            copies: value("copies"),
+            due_days,
+            payment_stub: query_flag(query, "payment_stub"),
+            thai_digits: query_flag(query, "thai_digits"),
+            skip_invalid: query_flag(query, "skip_invalid"),
+        })
+    }
+
+    // เติมตัวเลือกที่ไม่ได้กำหนดด้วยค่าของโปรไฟล์ที่เลือก
+    fn with_profile(self, profiles: &[Profile]) -> Result<Self> {
+        let Some(name) = &self.profile else {
+            return Ok(self);
+        };
+        let Some(profile) = profiles.iter().find(|profile| profile.name == *name) else {
+            return Err(Error::Validation(format!("ไม่พบโปรไฟล์ \"{}\"", name)));
+        };
+        let base = &profile.settings;
+        Ok(BillSettings {
+            month: self.month.or_else(|| base.month.clone()),
+            issue_date: self.issue_date.or_else(|| base.issue_date.clone()),
+            copies: self.copies.or_else(|| base.copies.clone()),
+            due_days: self.due_days.or(base.due_days),
+            payment_stub: self.payment_stub || base.payment_stub,
+            thai_digits: self.thai_digits || base.thai_digits,
+            skip_invalid: self.skip_invalid || base.skip_invalid,
+            profile: self.profile,
+        })
+    }
+
+    fn bill_options(&self) -> Result<(YearMonth, BillOptions)> {
+        let issue_date = match &self.issue_date {
+            Some(text) => parse_date(text).map_err(Error::Validation)?,
+            None => chrono::Local::now().date_naive(),
+        };
+        let period = match &self.month {
+            Some(text) => text.parse::<YearMonth>().map_err(Error::Validation)?,
+            None => YearMonth::from_date(issue_date),
+        };
+        let copy_mode = match &self.copies {
+            Some(text) => CopyMode::from_str(text, true)
+                .map_err(|_| Error::Validation(format!("ไม่รู้จักรูปแบบสำเนา \"{}\"", text)))?,
+            None => CopyMode::Single,
+        };
+        let defaults = BillOptions::default();
+        Ok((
+            period,
+            BillOptions {
+                copy_mode,
+                payment_stub: self.payment_stub,
+                due_days: self.due_days.unwrap_or(defaults.due_days),
+                issue_date,
+                thai_digits: self.thai_digits,
+                ..defaults
+            },
+        ))
+    }
+
+    // รายการที่ใช้ออกบิล ถ้ามีแถวผิดพลาดต้องเลือกข้ามแถวผิดพลาดก่อน
+    // แล้วเตรียมบิลด้วยขั้นตอนเดียวกับบรรทัดคำสั่ง
+    fn prepare(
+        &self,
+        report: ReadReport,
+        billing: &BillingSettings,
+    ) -> Result<(MonthlyBills, BillOptions)> {
+        let (period, mut options) = self.bill_options()?;
+        let records = report.into_records(self.skip_invalid)?;
+        if records.is_empty() {
+            return Err(Error::Validation("ไม่มีรายการที่จะออกบิล".to_string()));
+        }
+        let bills = prepare_bills(records, period, None, billing, &mut options)?;
+        Ok((bills, options))
+    }
+}
+
+/// รูปแบบผลลัพธ์ของ /api/bills: PDF ไฟล์เดียว หรือ zip ที่มี PDF แยกรายบิล
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ApiFormat {
+    #[default]
+    Pdf,
+    Zip,
+}
+
+/// คำขอของ JSON API: รายการบิล (ชื่อฟิลด์เหมือนไฟล์ JSON) และตัวเลือกใบเสร็จ
+#[derive(Debug, Deserialize)]
+struct ApiRequest {
+    records: Vec<serde_json::Value>,
+    #[serde(flatten)]
+    settings: BillSettings,
+    #[serde(default)]
+    format: ApiFormat,
+    /// ชื่อไฟล์แต่ละบิลใน zip ใช้ {month} {order} {meter} {name} ได้เหมือน --per-customer
+    name_pattern: Option<String>,
+}
+
+fn read_api_request(
+    request: &mut Request,
+    profiles: &[Profile],
+) -> Result<(ApiRequest, ReadReport)> {
+    let mut request: ApiRequest = serde_json::from_slice(&read_body(request)?)?;
+    request.settings = request.settings.with_profile(profiles)?;
+    let report = records_from_json(&request.records);
+    report.log_errors("API");
+    Ok((request, report))
+}
+
+fn bills_response(
+    report: ReadReport,
+    settings: &BillSettings,
+    billing: &BillingSettings,
+) -> Result<HttpResponse> {
+    let (mut bills, options) = settings.prepare(report, billing)?;
+    let period = bills.period;
+    let for_month = period.label();
+
+    log::log_info(&format!(
+        "สร้างใบเสร็จประจำเดือน {} จำนวน {} รายการ",
+        period.long_label(),
+        bills.records.len()
+    ));
+    let bytes = create_pdf_bytes(&bills.records, &for_month, &options)?;
+    bills.save(None, &for_month, &options, billing)?;
+    Ok(download_response(
+        bytes,
+        "application/pdf",
+        &format!("plumbing_{}.pdf", period),
+    ))
+}
+
+// PDF แยกไฟล์ละหนึ่งบิล รวมไว้ใน zip ไฟล์เดียว
+fn zip_response(
+    report: ReadReport,
+    settings: &BillSettings,
+    billing: &BillingSettings,
+    name_pattern: &str,
+) -> Result<HttpResponse> {
+    let (mut bills, options) = settings.prepare(report, billing)?;
+    let period = bills.period;
+    let for_month = period.label();
+
+    log::log_info(&format!(
+        "สร้างใบเสร็จแยกรายบิลประจำเดือน {} จำนวน {} ไฟล์",
+        period.long_label(),
+        bills.records.len()
+    ));
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let mut names = HashSet::new();
+    for bill in &bills.records {
+        let name = zip_entry_name(&per_customer_path(name_pattern, &for_month, bill));
+        if name.is_empty() || !names.insert(name.clone()) {
+            return Err(Error::Validation(format!(
+                "name_pattern \"{}\" ได้ชื่อไฟล์ว่างหรือซ้ำกัน ({}) ใส่ {{order}} หรือ {{meter}} ในชื่อไฟล์",
+                name_pattern, bill.meter_number
+            )));
+        }
+        let bytes = create_pdf_bytes(std::slice::from_ref(bill), &for_month, &options)?;
+        zip.start_file(name, SimpleFileOptions::default())
+            .map_err(io::Error::from)?;
+        zip.write_all(&bytes)?;
+    }
+    let bytes = zip.finish().map_err(io::Error::from)?.into_inner();
+    bills.save(None, &for_month, &options, billing)?;
+    Ok(download_response(
+        bytes,
+        "application/zip",
+        &format!("plumbing_{}.zip", period),
+    ))
+}
+
+// ชื่อไฟล์ใน zip ต้องอยู่ใต้โฟลเดอร์ที่แตก zip เสมอ ตัดรากของ path, "." และ ".." ออก
+// และแทน : ด้วย _ (ชื่อไดรฟ์บน Windows) ส่วนโฟลเดอร์ย่อยยังใช้ได้
+fn zip_entry_name(name: &str) -> String {
+    name.split(['/', '\\'])
+        .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+        .map(|part| part.replace(':', "_"))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// ยอดรวมทั้งเดือน บิลที่คำนวณแล้วพร้อมเลขที่บิล และเลขอ่านที่ควรตรวจสอบ (ไม่บันทึกประวัติ)
+fn summary_json(
+    report: ReadReport,
+    settings: &BillSettings,
+    billing: &BillingSettings,
+) -> Result<serde_json::Value> {
+    let (bills, options) = settings.prepare(report, billing)?;
+    let for_month = bills.period.label();
+    let anomalies: Vec<_> = bills
+        .anomalies
+        .iter()
+        .map(|anomaly| {
+            json!({
+                "order": anomaly.order,
+                "meter_number": anomaly.meter_number,
+                "name": anomaly.name,
+                "units": anomaly.units,
+                "average": anomaly.average,
+                "message": anomaly.kind.description(),
+            })
+        })
+        .collect();
+    Ok(json!({
+        "for_month": for_month,
+        "issue_date": options.issue_date,
+        "summary": BillSummary::from_records(&bills.records),
+        "bills": ComputedBill::from_records(&bills.records, &for_month, options.issue_date),
+        "anomalies": anomalies,
+    }))
+}
+
+// ชื่อไฟล์เป็นตัวอักษร ASCII (เดือนแบบ YYYY-MM) เพื่อให้ใช้ใน header ได้
+fn download_response(bytes: Vec<u8>, content_type: &str, file_name: &str) -> HttpResponse {
+    Response::from_data(bytes)
+        .with_header(header("Content-Type", content_type))
+        .with_header(header(
+            "Content-Disposition",
+            &format!("attachment; filename=\"{}\"", file_name),
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zip_entry_name_stays_inside_the_archive() {
+        assert_eq!(zip_entry_name("001-M001.pdf"), "001-M001.pdf");
+        assert_eq!(
+            zip_entry_name("ต.ค. 68/001-M001.pdf"),
+            "ต.ค. 68/001-M001.pdf"
+        );
+        assert_eq!(zip_entry_name("/etc/passwd"), "etc/passwd");
+        assert_eq!(zip_entry_name("../../001.pdf"), "001.pdf");
+        assert_eq!(zip_entry_name("a/./../b\\..\\001.pdf"), "a/b/001.pdf");
+        assert_eq!(zip_entry_name("C:\\bills\\001.pdf"), "C_/bills/001.pdf");
+        assert_eq!(zip_entry_name("../.."), "");
+    }
+
+    fn post(path: &str, headers: &[(&str, &str)]) -> Request {
+        headers
+            .iter()
+            .fold(
+                tiny_http::TestRequest::new()
+                    .with_method(Method::Post)
+                    .with_path(path),
+                |request, (name, value)| request.with_header(header(name, value)),
+            )
+            .into()
+    }
+
+    #[test]
+    fn allowed_hosts_follow_the_bind_address() {
+        let local = allowed_hosts("127.0.0.1:8080").unwrap();
+        assert!(local.contains(&"127.0.0.1:8080".to_string()));
+        assert!(local.contains(&"localhost:8080".to_string()));
+        assert!(!local.contains(&"evil.example:8080".to_string()));
+        assert_eq!(
+            allowed_hosts("192.168.1.10:8080").unwrap(),
+            ["192.168.1.10:8080"]
+        );
+        assert!(allowed_hosts("127.0.0.1:80")
+            .unwrap()
+            .contains(&"localhost".to_string()));
+        assert!(allowed_hosts("0.0.0.0:8080").is_none());
+    }
+
+    #[test]
+    fn cross_site_posts_are_rejected() {
+        let hosts = allowed_hosts("127.0.0.1:8080");
+        let check = |request: Request| check_request_source(&request, hosts.as_deref());
+        let local = ("Host", "127.0.0.1:8080");
+        let json = ("Content-Type", "application/json; charset=utf-8");
+        let page = ("X-Requested-With", "papa_moo_3_bills");
+
+        // หน้าเว็บของโปรแกรมเอง และสคริปต์ที่เรียก API (ไม่มี Origin)
+        assert!(check(post(
+            "/bills",
+            &[local, ("Origin", "http://127.0.0.1:8080"), page]
+        ))
+        .is_ok());
+        assert!(check(post("/api/bills", &[local, json])).is_ok());
+        assert!(check(
+            tiny_http::TestRequest::new()
+                .with_header(header("Host", "evil.example"))
+                .into()
+        )
+        .is_ok());
+
+        // ฟอร์ม text/plain จากเว็บอื่น
+        let form = ("Content-Type", "text/plain");
+        assert!(check(post(
+            "/api/bills",
+            &[local, ("Origin", "https://evil.example"), form]
+        ))
+        .is_err());
+        assert!(check(post("/api/bills", &[local, form])).is_err());
+        assert!(check(post("/bills", &[local, form])).is_err());
+        // มีหัวครบแต่มาจากเว็บอื่น หรือใช้ชื่อโดเมนอื่นชี้มาที่เครื่องนี้
+        assert!(check(post("/api/bills", &[local, ("Origin", "null"), json])).is_err());
+        assert!(check(post("/bills", &[("Host", "evil.example:8080"), page])).is_err());
+        assert!(check(post("/bills", &[page])).is_err());
+    }
+
+    #[test]
+    fn profile_fills_unset_settings() {
+        let profiles: Vec<Profile> = serde_json::from_str(
+            r#"[{"name": "สำเนา", "copies": "same-sheet", "due_days": 10, "payment_stub": true}]"#,
+        )
+        .unwrap();
+        let settings = BillSettings {
+            profile: Some("สำเนา".to_string()),
+            due_days: Some(20),
+            ..BillSettings::default()
+        }
+        .with_profile(&profiles)
+        .unwrap();
+        assert_eq!(settings.copies.as_deref(), Some("same-sheet"));
+        assert_eq!(settings.due_days, Some(20));
+        assert!(settings.payment_stub);
+        assert!(!settings.thai_digits);
+
+        let unknown = BillSettings {
+            profile: Some("ไม่มี".to_string()),
+            ..BillSettings::default()
+        };
+        assert!(matches!(
+            unknown.with_profile(&profiles),
+            Err(Error::Validation(_))
+        ));
+    }
+}